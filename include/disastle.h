@@ -0,0 +1,80 @@
+#ifndef DISASTLE_H
+#define DISASTLE_H
+
+/* Generated by cbindgen from src/ffi.rs. Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct DisastleGame DisastleGame;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Create a game from a JSON array of player secrets and a JSON `GameSetting`.
+ * Returns null if either argument is malformed or the setting cannot deal a game for the
+ * players.
+ *
+ * # Safety
+ * Both arguments must be null or valid NUL-terminated strings.
+ */
+DisastleGame *disastle_game_new(const char *players, const char *setting);
+
+/**
+ * Load a game saved with `disastle_game_to_json`, from any supported schema version.
+ * Returns null if the state can't be loaded.
+ *
+ * # Safety
+ * `state` must be null or a valid NUL-terminated string.
+ */
+DisastleGame *disastle_game_from_json(const char *state);
+
+/**
+ * Serialize the full game state, including hidden information, for saving.
+ *
+ * # Safety
+ * `game` must be a live handle returned by this module.
+ */
+char *disastle_game_to_json(const DisastleGame *game);
+
+/**
+ * The JSON `PlayerView` for `secret`; unknown secrets get the spectator view.
+ *
+ * # Safety
+ * `game` must be a live handle and `secret` a valid NUL-terminated string.
+ */
+char *disastle_game_player_view(const DisastleGame *game, const char *secret);
+
+/**
+ * Apply a JSON `Action` in place. Returns null on success, or a JSON `ErrorBody`
+ * describing why the action was rejected, in which case the game is unchanged.
+ *
+ * # Safety
+ * `game` must be a live handle; `secret` and `action` valid NUL-terminated strings.
+ */
+char *disastle_game_action(DisastleGame *game,
+                           const char *secret,
+                           uint64_t expected_seq,
+                           const char *action);
+
+/**
+ * # Safety
+ * `game` must be null or a handle returned by this module that has not been freed yet.
+ */
+void disastle_game_free(DisastleGame *game);
+
+/**
+ * # Safety
+ * `s` must be null or a string returned by this module that has not been freed yet.
+ */
+void disastle_string_free(char *s);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* DISASTLE_H */
@@ -0,0 +1,248 @@
+//! Terminal UI for a local hotseat game.
+//!
+//! Up/Down select one of the turn player's legal actions, Enter plays it, q quits.
+
+use disastle_castle_rust::Action;
+use disastle_rust::{
+    castle::{CastleExt, ASCII_LEGEND},
+    game::{GameError, GameSetting, GameState, PlayerSecret},
+    load_disasters, load_rooms, load_thrones, Pos,
+};
+use ratatui::{
+    backend::CrosstermBackend,
+    crossterm::{
+        event::{self, Event, KeyCode, KeyEventKind},
+        execute,
+        terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+    },
+    layout::{Constraint, Direction, Layout},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    Frame, Terminal,
+};
+use std::{env, io, panic, path::Path, process};
+
+struct App {
+    players: Vec<PlayerSecret>,
+    game: GameState,
+    actions: Vec<Action>,
+    selected: ListState,
+    message: String,
+}
+
+impl App {
+    fn new(players: Vec<PlayerSecret>, game: GameState) -> App {
+        let mut app = App {
+            players,
+            game,
+            actions: Vec::new(),
+            selected: ListState::default(),
+            message: String::new(),
+        };
+        app.refresh();
+        app
+    }
+    fn turn_player(&self) -> Option<&PlayerSecret> {
+        self.players.iter().find(|p| self.game.is_turn_player(p))
+    }
+    fn refresh(&mut self) {
+        self.actions = match self.turn_player() {
+            Some(player) if !self.game.is_over() => self.game.possible_actions(player),
+            _ => Vec::new(),
+        };
+        self.selected.select(if self.actions.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+    fn select(&mut self, offset: isize) {
+        if let Some(i) = self.selected.selected() {
+            let len = self.actions.len() as isize;
+            self.selected
+                .select(Some((i as isize + offset).rem_euclid(len) as usize));
+        }
+    }
+    fn play_selected(&mut self) {
+        let (player, action) = match (self.turn_player(), self.selected.selected()) {
+            (Some(player), Some(i)) => (player.clone(), self.actions[i].clone()),
+            _ => return,
+        };
+        match self
+            .game
+            .action(&player, self.game.get_action_seq(), action.clone())
+        {
+            Ok(game) => {
+                self.message = format!("{} played {}", player.as_str(), describe(&action));
+                self.game = game;
+            }
+            Err(e) => self.message = e.to_string(),
+        }
+        self.refresh();
+    }
+}
+
+fn describe(action: &Action) -> String {
+    match action {
+        Action::Place(index, pos) => format!("place shop [{}] at {}", index, Pos::from(*pos)),
+        Action::Move(from, to) => format!("move {} to {}", Pos::from(*from), Pos::from(*to)),
+        Action::Swap(a, b) => format!("swap {} and {}", Pos::from(*a), Pos::from(*b)),
+        Action::Discard(pos) => format!("discard {}", Pos::from(*pos)),
+    }
+}
+
+fn castles_text(app: &App) -> String {
+    let view = app.game.player_view(&app.players[0]);
+    let mut text = String::new();
+    for player in &app.players {
+        // Castles are listed by seat, which follows the secrets rather than creation order
+        let castle = &view.castles[app.game.get_seat(player).unwrap()];
+        let status = if castle.is_lost() { " (lost)" } else { "" };
+        text += &format!(
+            "{}{}: treasure {}, damage {}\n",
+            player.as_str(),
+            status,
+            castle.get_treasure(),
+            castle.damage
+        );
+        text += &castle.render_ascii();
+        text += "\n";
+    }
+    text + ASCII_LEGEND
+}
+
+fn table_text(app: &App) -> String {
+    let game = &app.game;
+    let mut text = format!("Round {}\n\nShop:\n", game.round);
+    for (i, room) in game.shop.iter().enumerate() {
+        text += &format!("  [{}] {} {:?}\n", i, room.name, room.connections);
+    }
+    text += "\nDisasters:\n";
+    for disaster in &game.previous_disasters {
+        text += &format!("  {}\n", disaster);
+    }
+    if game.is_over() {
+        text += "\nGame over. Winners:\n";
+        for player in app.players.iter().filter(|p| game.is_victorious(p)) {
+            text += &format!("  {}\n", player.as_str());
+        }
+    }
+    text
+}
+
+fn ui(f: &mut Frame, app: &mut App) {
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(f.size());
+    let right = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(40),
+            Constraint::Min(3),
+            Constraint::Length(3),
+        ])
+        .split(columns[1]);
+    f.render_widget(
+        Paragraph::new(castles_text(app))
+            .block(Block::default().title("Castles").borders(Borders::ALL)),
+        columns[0],
+    );
+    f.render_widget(
+        Paragraph::new(table_text(app))
+            .block(Block::default().title("Table").borders(Borders::ALL)),
+        right[0],
+    );
+    let title = match app.turn_player() {
+        Some(player) => format!("{} to act", player.as_str()),
+        None => "Actions".to_string(),
+    };
+    let items: Vec<ListItem> = app
+        .actions
+        .iter()
+        .map(|a| ListItem::new(describe(a)))
+        .collect();
+    f.render_stateful_widget(
+        List::new(items)
+            .block(Block::default().title(title).borders(Borders::ALL))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED)),
+        right[1],
+        &mut app.selected,
+    );
+    f.render_widget(
+        Paragraph::new(app.message.clone()).block(Block::default().borders(Borders::ALL)),
+        right[2],
+    );
+}
+
+fn run(app: &mut App) -> io::Result<()> {
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+    loop {
+        terminal.draw(|f| ui(f, app))?;
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up => app.select(-1),
+                KeyCode::Down => app.select(1),
+                KeyCode::Enter => app.play_selected(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Put the terminal back before a panic message is printed, so it stays readable and the
+/// shell usable.
+fn restore_terminal_on_panic() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        default_hook(info);
+    }));
+}
+
+fn new_game(players: usize) -> io::Result<App> {
+    let setting = GameSetting::base(
+        load_thrones(Path::new("thrones.ron"))?,
+        load_rooms(Path::new("rooms.ron"))?,
+        load_disasters(Path::new("disasters.ron"))?,
+    );
+    let players: Vec<PlayerSecret> = (1..=players)
+        .map(|n| PlayerSecret::from(format!("Player {}", n)))
+        .collect();
+    let game = GameState::new(players.clone(), setting).map_err(|errors| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            GameError::from(errors).to_string(),
+        )
+    })?;
+    Ok(App::new(players, game))
+}
+
+fn main() {
+    let players = match env::args().nth(1).map(|n| n.parse::<usize>()) {
+        None => 2,
+        Some(Ok(n)) if n >= 2 => n,
+        _ => {
+            eprintln!("usage: disastle-tui [players]");
+            process::exit(2);
+        }
+    };
+    let result = new_game(players).and_then(|mut app| {
+        restore_terminal_on_panic();
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let result = run(&mut app);
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        result
+    });
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
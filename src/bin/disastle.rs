@@ -0,0 +1,216 @@
+//! Local hotseat game in the terminal.
+//!
+//! ```text
+//! disastle play --players 3 --rooms rooms.ron --thrones thrones.ron --disasters disasters.ron
+//! ```
+
+use disastle_castle_rust::Action;
+use disastle_rust::{
+    castle::{CastleExt, ASCII_LEGEND},
+    game::{GameError, GameSetting, GameState, PlayerSecret},
+    load_disasters, load_rooms, load_thrones, Pos,
+};
+use std::{
+    env,
+    io::{self, BufRead, Write},
+    path::PathBuf,
+    process,
+};
+
+const USAGE: &str =
+    "usage: disastle play [--players N] [--rooms PATH] [--thrones PATH] [--disasters PATH]
+
+actions:
+  place <shop index> <x> <y>
+  move <x> <y> <x> <y>
+  swap <x> <y> <x> <y>
+  discard <x> <y>
+  actions    list every legal action
+  quit";
+
+struct Options {
+    players: usize,
+    rooms: PathBuf,
+    thrones: PathBuf,
+    disasters: PathBuf,
+}
+
+fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    if args.next().as_deref() != Some("play") {
+        return Err(USAGE.to_string());
+    }
+    let mut options = Options {
+        players: 2,
+        rooms: PathBuf::from("rooms.ron"),
+        thrones: PathBuf::from("thrones.ron"),
+        disasters: PathBuf::from("disasters.ron"),
+    };
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("missing value for {}", flag))?;
+        match flag.as_str() {
+            "--players" => {
+                options.players = value
+                    .parse()
+                    .map_err(|_| format!("invalid player count {}", value))?
+            }
+            "--rooms" => options.rooms = PathBuf::from(value),
+            "--thrones" => options.thrones = PathBuf::from(value),
+            "--disasters" => options.disasters = PathBuf::from(value),
+            _ => return Err(format!("unknown option {}\n\n{}", flag, USAGE)),
+        }
+    }
+    if options.players < 2 {
+        return Err("a game needs at least 2 players".to_string());
+    }
+    Ok(options)
+}
+
+fn new_game(options: &Options) -> io::Result<(Vec<PlayerSecret>, GameState)> {
+    let setting = GameSetting::base(
+        load_thrones(&options.thrones)?,
+        load_rooms(&options.rooms)?,
+        load_disasters(&options.disasters)?,
+    );
+    let players: Vec<PlayerSecret> = (1..=options.players)
+        .map(|n| PlayerSecret::from(format!("Player {}", n)))
+        .collect();
+    let game = GameState::new(players.clone(), setting).map_err(|errors| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            GameError::from(errors).to_string(),
+        )
+    })?;
+    Ok((players, game))
+}
+
+fn parse_numbers(words: &[&str]) -> Option<Vec<i32>> {
+    words.iter().map(|w| w.parse().ok()).collect()
+}
+
+/// Parse one line of action notation, e.g. `place 2 0 1` or `discard 1 0`.
+fn parse_action(line: &str) -> Option<Action> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let (verb, rest) = words.split_first()?;
+    let n = parse_numbers(rest)?;
+    match (*verb, n.as_slice()) {
+        ("place", &[index, x, y]) if index >= 0 => {
+            Some(Action::Place(index as usize, Pos::new(x, y).into()))
+        }
+        ("move", &[x1, y1, x2, y2]) => Some(Action::Move(
+            Pos::new(x1, y1).into(),
+            Pos::new(x2, y2).into(),
+        )),
+        ("swap", &[x1, y1, x2, y2]) => Some(Action::Swap(
+            Pos::new(x1, y1).into(),
+            Pos::new(x2, y2).into(),
+        )),
+        ("discard", &[x, y]) => Some(Action::Discard(Pos::new(x, y).into())),
+        _ => None,
+    }
+}
+
+fn print_table(game: &GameState, players: &[PlayerSecret]) {
+    println!("\n=== Round {} ===", game.round);
+    for (i, disaster) in game.previous_disasters.iter().enumerate() {
+        println!("Disaster {}: {}", i + 1, disaster);
+    }
+    let view = game.player_view(&players[0]);
+    for player in players {
+        // Castles are listed by seat, which follows the secrets rather than creation order
+        let castle = &view.castles[game.get_seat(player).unwrap()];
+        let status = if castle.is_lost() { " (lost)" } else { "" };
+        println!(
+            "{}{}: treasure {}, damage {}",
+            player.as_str(),
+            status,
+            castle.get_treasure(),
+            castle.damage
+        );
+        println!("{}", castle.render_ascii());
+        for (pos, room) in &castle.rooms {
+            println!("  {} {}", Pos::from(*pos), room.name);
+        }
+    }
+    println!("{}", ASCII_LEGEND);
+    println!("Shop ({} cards left in deck):", view.deck_size);
+    for (i, room) in game.shop.iter().enumerate() {
+        println!("  [{}] {} {:?}", i, room.name, room.connections);
+    }
+}
+
+fn play(options: Options) -> io::Result<()> {
+    let (players, mut game) = new_game(&options)?;
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    print_table(&game, &players);
+    while !game.is_over() {
+        let player = match players.iter().find(|p| game.is_turn_player(p)) {
+            Some(player) => player,
+            None => break,
+        };
+        let castle_damage = game.player_view(player).castles[game.get_seat(player).unwrap()].damage;
+        if castle_damage > 0 {
+            print!(
+                "{} must discard {} damage> ",
+                player.as_str(),
+                castle_damage
+            );
+        } else {
+            print!("{}> ", player.as_str());
+        }
+        io::stdout().flush()?;
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(()),
+        };
+        match line.trim() {
+            "" => continue,
+            "quit" => return Ok(()),
+            "actions" => {
+                for action in game.possible_actions(player) {
+                    println!("  {:?}", action);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        let action = match parse_action(&line) {
+            Some(action) => action,
+            None => {
+                println!("{}", USAGE);
+                continue;
+            }
+        };
+        match game.action(player, game.get_action_seq(), action) {
+            Ok(next) => {
+                let round = game.round;
+                game = next;
+                if game.round != round {
+                    print_table(&game, &players);
+                }
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+    print_table(&game, &players);
+    for player in players.iter().filter(|p| game.is_victorious(p)) {
+        println!("{} wins!", player.as_str());
+    }
+    Ok(())
+}
+
+fn main() {
+    let options = match parse_options(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = play(options) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,54 @@
+//! Compact bincode encoding of the game types, for storage and transport where the size of
+//! RON or JSON matters.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use bincode::Error;
+
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, Error> {
+    bincode::serialize(value)
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, Error> {
+    bincode::deserialize(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::{
+        disaster::Disaster,
+        game::{test_game, GameState, SchrodingerGameState, StateDelta, Versioned},
+        load_disasters,
+    };
+    use std::path::Path;
+
+    #[test]
+    fn test_game_state_round_trip() {
+        let game = test_game(&["a", "b", "c"]);
+        let decoded: GameState = decode(&encode(&game).unwrap()).unwrap();
+        assert_eq!(decoded, game);
+        let versioned: Versioned<GameState> =
+            decode(&encode(&game.to_versioned()).unwrap()).unwrap();
+        assert_eq!(versioned.state, game);
+    }
+    #[test]
+    fn test_schrodinger_round_trip() {
+        let game = test_game(&["a", "b"]).to_schrodinger();
+        let decoded: SchrodingerGameState = decode(&encode(&game).unwrap()).unwrap();
+        assert_eq!(decoded, game);
+    }
+    #[test]
+    fn test_delta_round_trip() {
+        let game = test_game(&["a", "b"]);
+        let delta = game.next_turn().diff(&game);
+        let decoded: StateDelta = decode(&encode(&delta).unwrap()).unwrap();
+        assert_eq!(decoded, delta);
+    }
+    #[test]
+    fn test_disasters_round_trip() {
+        let disasters = load_disasters(Path::new("disasters.ron")).unwrap();
+        let decoded: Vec<Disaster> = decode(&encode(&disasters).unwrap()).unwrap();
+        assert_eq!(decoded, disasters);
+    }
+}
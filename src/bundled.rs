@@ -0,0 +1,19 @@
+//! Card data of the base game embedded with `include_str!`, for deployments that can't ship
+//! the data files alongside the binary. `GameSetting::standard` builds a setting from it.
+
+pub const ROOMS: &str = include_str!("../rooms.ron");
+pub const THRONES: &str = include_str!("../thrones.ron");
+pub const DISASTERS: &str = include_str!("../disasters.ron");
+
+#[cfg(test)]
+mod tests {
+    use crate::game::GameSetting;
+
+    #[test]
+    fn test_standard_setting() {
+        let setting = GameSetting::standard();
+        assert_eq!(setting.rooms.len(), 100);
+        assert_eq!(setting.thrones.len(), 10);
+        assert_eq!(setting.disasters.len(), 12);
+    }
+}
@@ -0,0 +1,61 @@
+use super::{connections, is_link};
+use crate::pos::Pos;
+use disastle_castle_rust::{Castle, CastleError, Room};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum BuildError {
+    #[error("The castle has no throne")]
+    NoThrone,
+    #[error("Room at {0} is not linked to the throne")]
+    Disconnected(Pos),
+    #[error("Castle error: {error} while placing the room at {pos}")]
+    CastleError { pos: Pos, error: CastleError },
+}
+
+/// Build a castle holding `rooms` by starting from the throne and placing every other room
+/// through `Castle::place_room`, in breadth-first order along links, so the result goes
+/// through the same validation as rooms placed during a game. The layout is translated so
+/// the throne sits where `Castle::new` puts it.
+pub fn from_rooms(rooms: BTreeMap<Pos, Room>) -> Result<Castle, BuildError> {
+    let (&throne_pos, throne) = rooms
+        .iter()
+        .find(|(_, room)| room.throne)
+        .ok_or(BuildError::NoThrone)?;
+    let mut castle = Castle::new(throne.clone());
+    let anchor = castle
+        .rooms
+        .keys()
+        .next()
+        .map_or(Pos::ORIGIN, |pos| Pos::from(*pos));
+    let mut placed = BTreeSet::new();
+    let mut queue = VecDeque::new();
+    placed.insert(throne_pos);
+    queue.push_back(throne_pos);
+    while let Some(pos) = queue.pop_front() {
+        let own = connections(&rooms[&pos]);
+        for (i, neighbor) in pos.neighbors().iter().enumerate() {
+            let room = match rooms.get(neighbor) {
+                Some(room) if !placed.contains(neighbor) => room,
+                _ => continue,
+            };
+            if !is_link(&own[i], &connections(room)[(i + 2) % 4]) {
+                continue;
+            }
+            let relative = neighbor.offset(anchor.x - throne_pos.x, anchor.y - throne_pos.y);
+            castle = castle
+                .place_room(room.clone(), relative.into())
+                .map_err(|error| BuildError::CastleError {
+                    pos: *neighbor,
+                    error,
+                })?;
+            placed.insert(*neighbor);
+            queue.push_back(*neighbor);
+        }
+    }
+    match rooms.keys().find(|pos| !placed.contains(pos)) {
+        Some(pos) => Err(BuildError::Disconnected(*pos)),
+        None => Ok(castle),
+    }
+}
@@ -0,0 +1,187 @@
+//! Compact, URL-safe codes describing a castle layout.
+//!
+//! A code is the unpadded URL-safe base64 of: a format version byte, the room count, then
+//! for every room its index in the catalog, its position and its rotation. Counts and
+//! indices are LEB128 varints, coordinates zigzag-encoded varints.
+
+use super::{build::from_rooms, BuildError};
+use crate::pos::Pos;
+use crate::room::{rotation_from, RoomExt};
+use disastle_castle_rust::{Castle, Room};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+const VERSION: u8 = 1;
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+#[derive(Debug, Error)]
+pub enum CodeError {
+    #[error("Room {0} is not in the catalog")]
+    RoomNotInCatalog(String),
+    #[error("Invalid character {0:?} in castle code")]
+    InvalidCharacter(char),
+    #[error("Castle code version {0} is not supported")]
+    UnsupportedVersion(u8),
+    #[error("Castle code is truncated")]
+    Truncated,
+    #[error("Catalog has no room {0}")]
+    UnknownRoom(usize),
+    #[error("Rotation {0} is not between 0 and 3")]
+    InvalidRotation(u8),
+    #[error(transparent)]
+    Build(#[from] BuildError),
+}
+
+/// Catalog index of the room `room` is a rotation of.
+fn find(catalog: &[Room], room: &Room) -> Option<usize> {
+    catalog
+        .iter()
+        .position(|base| rotation_from(base, room).is_some())
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u32) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(bytes: &mut impl Iterator<Item = u8>) -> Result<u32, CodeError> {
+    let mut value = 0;
+    for shift in (0..35).step_by(7) {
+        let byte = bytes.next().ok_or(CodeError::Truncated)?;
+        value |= ((byte & 0x7f) as u32) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(CodeError::Truncated)
+}
+
+fn zigzag(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn unzigzag(value: u32) -> i32 {
+    (value >> 1) as i32 ^ -((value & 1) as i32)
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..=chunk.len() {
+            code.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    code
+}
+
+fn decode_base64(code: &str) -> Result<Vec<u8>, CodeError> {
+    let digits = code
+        .chars()
+        .map(|c| match ALPHABET.iter().position(|&a| a as char == c) {
+            Some(digit) => Ok(digit as u32),
+            None => Err(CodeError::InvalidCharacter(c)),
+        })
+        .collect::<Result<Vec<u32>, CodeError>>()?;
+    let mut bytes = Vec::new();
+    for chunk in digits.chunks(4) {
+        if chunk.len() < 2 {
+            return Err(CodeError::Truncated);
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, d)| n | d << (18 - 6 * i));
+        for i in 0..chunk.len() - 1 {
+            bytes.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Ok(bytes)
+}
+
+pub fn to_code(castle: &Castle, catalog: &[Room]) -> Result<String, CodeError> {
+    let mut bytes = vec![VERSION];
+    write_varint(&mut bytes, castle.rooms.len() as u32);
+    for (pos, room) in &castle.rooms {
+        let pos = Pos::from(*pos);
+        let index =
+            find(catalog, room).ok_or_else(|| CodeError::RoomNotInCatalog(room.name.clone()))?;
+        write_varint(&mut bytes, index as u32);
+        write_varint(&mut bytes, zigzag(pos.x));
+        write_varint(&mut bytes, zigzag(pos.y));
+        bytes.push(room.rotation % 4);
+    }
+    Ok(encode_base64(&bytes))
+}
+
+pub fn from_code(code: &str, catalog: &[Room]) -> Result<Castle, CodeError> {
+    let bytes = decode_base64(code)?;
+    let mut bytes = bytes.into_iter();
+    match bytes.next() {
+        Some(VERSION) => {}
+        Some(version) => return Err(CodeError::UnsupportedVersion(version)),
+        None => return Err(CodeError::Truncated),
+    }
+    let mut rooms = BTreeMap::new();
+    for _ in 0..read_varint(&mut bytes)? {
+        let index = read_varint(&mut bytes)? as usize;
+        let base = catalog.get(index).ok_or(CodeError::UnknownRoom(index))?;
+        let x = unzigzag(read_varint(&mut bytes)?);
+        let y = unzigzag(read_varint(&mut bytes)?);
+        let rotation = bytes.next().ok_or(CodeError::Truncated)?;
+        if rotation > 3 {
+            return Err(CodeError::InvalidRotation(rotation));
+        }
+        let turns = (rotation + 4 - base.rotation % 4) % 4;
+        rooms.insert(Pos::new(x, y), base.rotated(turns));
+    }
+    Ok(from_rooms(rooms)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::castle::{test_castles, CastleExt};
+    use crate::game::test_game;
+
+    #[test]
+    fn test_base64_and_varints() {
+        for bytes in [&b""[..], b"a", b"ab", b"abc", b"\xff\x00\x7f\x80"] {
+            assert_eq!(decode_base64(&encode_base64(bytes)).unwrap(), bytes);
+        }
+        for value in [0, 1, -1, 63, -64, i32::MAX, i32::MIN] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, zigzag(value));
+            assert_eq!(
+                unzigzag(read_varint(&mut bytes.into_iter()).unwrap()),
+                value
+            );
+        }
+        assert!(matches!(
+            decode_base64("ab!"),
+            Err(CodeError::InvalidCharacter('!'))
+        ));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let game = test_game(&["a", "b"]);
+        let catalog = game.setting.catalog();
+        for castle in test_castles() {
+            let code = castle.to_code(&catalog).unwrap();
+            assert_eq!(Castle::from_code(&code, &catalog).unwrap(), castle);
+        }
+
+        let code = encode_base64(&[VERSION, 1, 0, 0, 0, 200]);
+        assert!(matches!(
+            Castle::from_code(&code, &catalog),
+            Err(CodeError::InvalidRotation(200))
+        ));
+    }
+}
@@ -0,0 +1,142 @@
+use super::CastleExt;
+use crate::pos::Pos;
+use disastle_castle_rust::{Castle, Connection, Room};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, hash::Hasher};
+
+/// Inclusive bounding box of the positions in a castle.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bounds {
+    pub min: Pos,
+    pub max: Pos,
+}
+
+impl Bounds {
+    /// Smallest box containing every position, or `None` if there are none.
+    pub fn of(positions: impl IntoIterator<Item = Pos>) -> Option<Bounds> {
+        positions
+            .into_iter()
+            .fold(None, |bounds, pos| match bounds {
+                None => Some(Bounds { min: pos, max: pos }),
+                Some(Bounds { min, max }) => Some(Bounds {
+                    min: Pos::new(min.x.min(pos.x), min.y.min(pos.y)),
+                    max: Pos::new(max.x.max(pos.x), max.y.max(pos.y)),
+                }),
+            })
+    }
+    pub fn width(&self) -> i32 {
+        self.max.x - self.min.x + 1
+    }
+    pub fn height(&self) -> i32 {
+        self.max.y - self.min.y + 1
+    }
+    pub fn contains(&self, pos: Pos) -> bool {
+        (self.min.x..=self.max.x).contains(&pos.x) && (self.min.y..=self.max.y).contains(&pos.y)
+    }
+}
+
+/// 64-bit FNV-1a, used instead of `DefaultHasher` so hashes stay comparable across builds.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+/// Kind of the connection in the low bits, gold in the high bit.
+fn connection_tag(connection: &Connection) -> u8 {
+    match connection {
+        Connection::None => 0,
+        Connection::Diamond(gold) => 1 | (*gold as u8) << 7,
+        Connection::Cross(gold) => 2 | (*gold as u8) << 7,
+        Connection::Moon(gold) => 3 | (*gold as u8) << 7,
+        Connection::Wild => 4,
+    }
+}
+
+/// Hash of explicit bytes rather than of `Hash` impls, whose output may change between
+/// compiler and dependency versions: per room its coordinates as little-endian `i32`s, the
+/// length and bytes of its name, its throne flag, treasure and rotation, and a tag per
+/// connection.
+fn hash_layout(rooms: &BTreeMap<Pos, &Room>) -> u64 {
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    for (pos, room) in rooms {
+        hasher.write(&pos.x.to_le_bytes());
+        hasher.write(&pos.y.to_le_bytes());
+        hasher.write(&(room.name.len() as u32).to_le_bytes());
+        hasher.write(room.name.as_bytes());
+        hasher.write(&[room.throne as u8, room.treasure, room.rotation % 4]);
+        let (up, right, down, left) = &room.connections;
+        hasher.write(&[up, right, down, left].map(connection_tag));
+    }
+    hasher.finish()
+}
+
+pub fn canonical_hash(castle: &Castle) -> u64 {
+    // The lowest throne anchors castles with several
+    let origin = castle
+        .throne_positions()
+        .first()
+        .copied()
+        .unwrap_or(Pos::ORIGIN);
+    let rooms = castle
+        .iter()
+        .map(|(pos, room)| (pos.offset(-origin.x, -origin.y), room))
+        .collect();
+    hash_layout(&rooms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::castle::test_castles;
+    #[test]
+    fn test_bounds() {
+        assert_eq!(Bounds::of(Vec::new()), None);
+        let bounds = Bounds::of(vec![Pos::new(0, 0), Pos::new(-2, 1), Pos::new(1, -1)]).unwrap();
+        assert_eq!(bounds.min, Pos::new(-2, -1));
+        assert_eq!(bounds.max, Pos::new(1, 1));
+        assert_eq!((bounds.width(), bounds.height()), (4, 3));
+        assert!(bounds.contains(Pos::new(-2, -1)));
+        assert!(!bounds.contains(Pos::new(2, 0)));
+    }
+    #[test]
+    fn test_normalize() {
+        for castle in test_castles() {
+            let mut moved = castle.clone();
+            moved.rooms = castle
+                .iter()
+                .map(|(pos, room)| (pos.offset(3, -2).into(), room.clone()))
+                .collect();
+            let normalized = moved.normalize().unwrap();
+            assert_eq!(normalized, castle.normalize().unwrap());
+            assert_eq!(moved.canonical_hash(), castle.canonical_hash());
+            assert_eq!(normalized.canonical_hash(), castle.canonical_hash());
+            let bounds = castle.bounds().unwrap();
+            assert_eq!(moved.bounds().unwrap().min, bounds.min.offset(3, -2));
+        }
+    }
+    #[test]
+    fn test_hash_is_pinned() {
+        let throne = Room {
+            throne: true,
+            treasure: 0,
+            name: "Throne".to_string(),
+            rotation: 0,
+            connections: (
+                Connection::Diamond(false),
+                Connection::Cross(true),
+                Connection::Moon(false),
+                Connection::Wild,
+            ),
+        };
+        let rooms = vec![(Pos::ORIGIN, &throne)].into_iter().collect();
+        assert_eq!(hash_layout(&rooms), 0x2ff7_72a8_5bfe_d358);
+    }
+}
@@ -0,0 +1,84 @@
+use super::{connections, is_gold, is_link, CastleExt};
+use crate::pos::{Direction, Pos};
+use disastle_castle_rust::{Castle, Connection};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Diamond,
+    Cross,
+    Moon,
+    /// Two wild connections facing each other.
+    Wild,
+}
+
+/// A link formed by one side of a room.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct Link {
+    pub direction: Direction,
+    pub kind: LinkKind,
+    /// Whether either side of the link is gold.
+    pub gold: bool,
+}
+
+fn kind(connection: &Connection) -> Option<LinkKind> {
+    match connection {
+        Connection::None => None,
+        Connection::Diamond(_) => Some(LinkKind::Diamond),
+        Connection::Cross(_) => Some(LinkKind::Cross),
+        Connection::Moon(_) => Some(LinkKind::Moon),
+        Connection::Wild => Some(LinkKind::Wild),
+    }
+}
+
+pub fn links_at(castle: &Castle, pos: Pos) -> Vec<Link> {
+    let own = match castle.room_at(pos) {
+        Some(room) => connections(room),
+        None => return Vec::new(),
+    };
+    let mut links = Vec::new();
+    for &direction in Direction::ALL.iter() {
+        let own = &own[direction.index()];
+        let other = match castle.room_at(pos.step(direction)) {
+            Some(other) => connections(other),
+            None => continue,
+        };
+        let facing = &other[direction.opposite().index()];
+        if !is_link(own, facing) {
+            continue;
+        }
+        // A wild side takes the suit of the connection it faces
+        let kind = match (kind(own), kind(facing)) {
+            (Some(LinkKind::Wild), Some(kind)) | (Some(kind), _) => kind,
+            (None, _) => continue,
+        };
+        links.push(Link {
+            direction,
+            kind,
+            gold: is_gold(own) || is_gold(facing),
+        });
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::castle::test_castles;
+
+    #[test]
+    fn test_links_are_symmetric() {
+        for castle in test_castles() {
+            for (pos, links) in castle.links_by_room() {
+                for link in links {
+                    let back = castle.links_at(pos.step(link.direction));
+                    assert!(back.contains(&Link {
+                        direction: link.direction.opposite(),
+                        ..link
+                    }));
+                }
+            }
+        }
+    }
+}
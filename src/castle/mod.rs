@@ -0,0 +1,296 @@
+mod build;
+mod code;
+mod layout;
+mod links;
+mod power;
+mod removal;
+mod render;
+#[cfg(feature = "render-svg")]
+pub mod svg;
+mod target;
+mod validate;
+
+use crate::{
+    disaster::Target,
+    pos::{Direction, Pos},
+    room::RoomCard,
+};
+use disastle_castle_rust::{Castle, Connection, Room};
+use rand::Rng;
+use std::collections::BTreeMap;
+
+pub use build::{from_rooms, BuildError};
+pub use code::CodeError;
+pub use layout::Bounds;
+pub use links::{Link, LinkKind};
+pub use power::is_gold;
+pub use render::ASCII_LEGEND;
+pub use validate::{compatible, is_link, ValidationError};
+
+/// Helpers on `disastle_castle_rust::Castle` that only need its public room map.
+pub trait CastleExt {
+    fn room_at(&self, pos: Pos) -> Option<&Room>;
+    fn contains(&self, pos: Pos) -> bool;
+    /// Every room with its position, in position order.
+    fn iter(&self) -> Box<dyn Iterator<Item = (Pos, &Room)> + '_>;
+    fn throne_positions(&self) -> Vec<Pos>;
+    /// Check that every pair of touching connections is compatible and every room is
+    /// linked to a throne, reporting every problem found.
+    fn validate(&self) -> Result<(), Vec<ValidationError>>;
+    /// Whether the room at `pos` has gold connections and every one of them forms a link.
+    fn is_powered(&self, pos: Pos) -> bool;
+    /// For the side of the room at `pos` facing `direction`: `None` if it is not gold,
+    /// otherwise whether it forms a link.
+    fn powered_side(&self, pos: Pos, direction: Direction) -> Option<bool>;
+    /// `powered_side` for every side, in the order of `Direction::ALL`.
+    fn powered_sides(&self, pos: Pos) -> [Option<bool>; 4];
+    /// Positions of every powered room.
+    fn powered_rooms(&self) -> Vec<Pos>;
+    /// Every link with a gold connection on at least one side, as pairs of positions with
+    /// the lower or left room first.
+    fn powered_links(&self) -> Vec<(Pos, Pos)>;
+    /// Links the room at `pos` forms with its neighbors, one per linked side.
+    fn links_at(&self, pos: Pos) -> Vec<Link>;
+    /// `links_at` for every room, including rooms forming no link.
+    fn links_by_room(&self) -> BTreeMap<Pos, Vec<Link>>;
+    /// The card of the room at `pos` among `cards`, matching rotated rooms.
+    fn card_at<'a>(&self, pos: Pos, cards: &'a [RoomCard]) -> Option<&'a RoomCard>;
+    /// Positions of the rooms whose card carries `tag`.
+    fn rooms_tagged(&self, tag: &str, cards: &[RoomCard]) -> Vec<Pos>;
+    /// Positions of the rooms that can be removed without cutting any other room off from
+    /// every throne, computed in linear time.
+    fn removable_rooms(&self) -> Vec<Pos>;
+    /// The removable room farthest from a throne, the last in position order on ties.
+    fn outer_room(&self) -> Option<Pos>;
+    /// Copy of the castle without the room at `pos`, rebuilt through `from_rooms`. Damage
+    /// is kept.
+    fn without_room(&self, pos: Pos) -> Result<Castle, BuildError>;
+    /// Number of links between each room and the nearest throne.
+    fn link_distances(&self) -> BTreeMap<Pos, u32>;
+    /// Rooms a targeted disaster may destroy, for previewing it: every room of a region, or
+    /// the candidates of a random pick.
+    fn targeted_rooms(&self, target: &Target) -> Vec<Pos>;
+    /// Copy of the castle with the rooms hit by `target` destroyed, along with those rooms.
+    fn strike<R: Rng + ?Sized>(
+        &self,
+        target: &Target,
+        rng: &mut R,
+    ) -> Result<(Castle, Vec<Room>), BuildError>;
+    /// Copy of the castle rebuilt from its room map alone through `from_rooms`, regenerating
+    /// the connection graph `disastle_castle_rust` keeps alongside it. Rooms keep their
+    /// positions and damage is kept.
+    fn rebuild_connections(&self) -> Result<Castle, BuildError>;
+    /// Bounding box of every room, `None` for a castle without rooms.
+    fn bounds(&self) -> Option<Bounds>;
+    /// Copy of the castle translated so the throne sits where `Castle::new` puts it.
+    fn normalize(&self) -> Result<Castle, BuildError>;
+    /// Hash of the rooms and their positions relative to the throne, equal for castles that
+    /// only differ by translation. Stable across builds, so it can be stored for analytics.
+    fn canonical_hash(&self) -> u64;
+    /// Grid-aligned text drawing of the castle with the highest row first. Each room is a
+    /// 7x3 cell showing its name and its connection symbols on the matching edge: `D`, `C`
+    /// and `M` for gold diamonds, crosses and moons, lowercase otherwise, and `*` for wild.
+    /// Thrones are drawn with `#` corners and powered rooms with `=` edges. `ASCII_LEGEND`
+    /// explains the symbols.
+    fn render_ascii(&self) -> String;
+    /// Standalone SVG image of the castle, with gold markers filled and powered links drawn
+    /// thicker.
+    #[cfg(feature = "render-svg")]
+    fn render_svg(&self) -> String;
+    /// Compact code for sharing the castle layout, identifying rooms by their index in
+    /// `catalog`, typically `GameSetting::catalog`. Rotated copies of catalog rooms are
+    /// recognised.
+    fn to_code(&self, catalog: &[Room]) -> Result<String, CodeError>;
+    /// Rebuild a castle from a code made by `to_code` with the same catalog. Damage is not
+    /// part of the code.
+    fn from_code(code: &str, catalog: &[Room]) -> Result<Self, CodeError>
+    where
+        Self: Sized;
+}
+
+impl CastleExt for Castle {
+    fn room_at(&self, pos: Pos) -> Option<&Room> {
+        self.rooms.get(&pos.into())
+    }
+    fn contains(&self, pos: Pos) -> bool {
+        self.rooms.contains_key(&pos.into())
+    }
+    fn iter(&self) -> Box<dyn Iterator<Item = (Pos, &Room)> + '_> {
+        Box::new(self.rooms.iter().map(|(pos, room)| (Pos::from(*pos), room)))
+    }
+    fn throne_positions(&self) -> Vec<Pos> {
+        self.iter()
+            .filter(|(_, room)| room.throne)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+    fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        validate::validate(self)
+    }
+    fn is_powered(&self, pos: Pos) -> bool {
+        power::is_powered(self, pos)
+    }
+    fn powered_side(&self, pos: Pos, direction: Direction) -> Option<bool> {
+        power::powered_side(self, pos, direction)
+    }
+    fn powered_sides(&self, pos: Pos) -> [Option<bool>; 4] {
+        let [up, right, down, left] = Direction::ALL;
+        [
+            self.powered_side(pos, up),
+            self.powered_side(pos, right),
+            self.powered_side(pos, down),
+            self.powered_side(pos, left),
+        ]
+    }
+    fn powered_rooms(&self) -> Vec<Pos> {
+        self.iter()
+            .map(|(pos, _)| pos)
+            .filter(|pos| self.is_powered(*pos))
+            .collect()
+    }
+    fn powered_links(&self) -> Vec<(Pos, Pos)> {
+        power::powered_links(self)
+    }
+    fn links_at(&self, pos: Pos) -> Vec<Link> {
+        links::links_at(self, pos)
+    }
+    fn links_by_room(&self) -> BTreeMap<Pos, Vec<Link>> {
+        self.iter()
+            .map(|(pos, _)| (pos, self.links_at(pos)))
+            .collect()
+    }
+    fn card_at<'a>(&self, pos: Pos, cards: &'a [RoomCard]) -> Option<&'a RoomCard> {
+        let room = self.room_at(pos)?;
+        cards.iter().find(|card| card.matches(room))
+    }
+    fn rooms_tagged(&self, tag: &str, cards: &[RoomCard]) -> Vec<Pos> {
+        self.iter()
+            .map(|(pos, _)| pos)
+            .filter(|pos| matches!(self.card_at(*pos, cards), Some(card) if card.has_tag(tag)))
+            .collect()
+    }
+    fn removable_rooms(&self) -> Vec<Pos> {
+        removal::removable_rooms(self)
+    }
+    fn outer_room(&self) -> Option<Pos> {
+        let thrones = self.throne_positions();
+        let distance = |pos: Pos| {
+            thrones
+                .iter()
+                .map(|&throne| pos.manhattan_distance(throne))
+                .min()
+        };
+        self.removable_rooms()
+            .into_iter()
+            .max_by_key(|&pos| (distance(pos), pos))
+    }
+    fn without_room(&self, pos: Pos) -> Result<Castle, BuildError> {
+        let rooms = self
+            .iter()
+            .filter(|&(p, _)| p != pos)
+            .map(|(p, room)| (p, room.clone()))
+            .collect();
+        let mut castle = from_rooms(rooms)?;
+        castle.damage = self.damage;
+        Ok(castle)
+    }
+    fn link_distances(&self) -> BTreeMap<Pos, u32> {
+        target::link_distances(self)
+    }
+    fn targeted_rooms(&self, target: &Target) -> Vec<Pos> {
+        target::targeted_rooms(self, target)
+    }
+    fn strike<R: Rng + ?Sized>(
+        &self,
+        target: &Target,
+        rng: &mut R,
+    ) -> Result<(Castle, Vec<Room>), BuildError> {
+        target::strike(self, target, rng)
+    }
+    fn rebuild_connections(&self) -> Result<Castle, BuildError> {
+        let mut castle = self.normalize()?;
+        // Translation keeps position order, so the first rooms of both castles match
+        let first = |castle: &Castle| castle.iter().next().map(|(pos, _)| pos);
+        if let (Some(stored), Some(built)) = (first(self), first(&castle)) {
+            let (dx, dy) = (stored.x - built.x, stored.y - built.y);
+            castle.rooms = castle
+                .iter()
+                .map(|(pos, room)| (pos.offset(dx, dy).into(), room.clone()))
+                .collect();
+        }
+        Ok(castle)
+    }
+    fn bounds(&self) -> Option<Bounds> {
+        Bounds::of(self.iter().map(|(pos, _)| pos))
+    }
+    fn normalize(&self) -> Result<Castle, BuildError> {
+        let rooms = self.iter().map(|(pos, room)| (pos, room.clone())).collect();
+        let mut castle = from_rooms(rooms)?;
+        castle.damage = self.damage;
+        Ok(castle)
+    }
+    fn canonical_hash(&self) -> u64 {
+        layout::canonical_hash(self)
+    }
+    fn render_ascii(&self) -> String {
+        render::ascii(self)
+    }
+    #[cfg(feature = "render-svg")]
+    fn render_svg(&self) -> String {
+        svg::castle(self)
+    }
+    fn to_code(&self, catalog: &[Room]) -> Result<String, CodeError> {
+        code::to_code(self, catalog)
+    }
+    fn from_code(code: &str, catalog: &[Room]) -> Result<Castle, CodeError> {
+        code::from_code(code, catalog)
+    }
+}
+
+/// A room's connections in the order up, right, down, left, matching `Pos::neighbors`.
+pub fn connections(room: &Room) -> [Connection; 4] {
+    let (up, right, down, left) = room.connections.clone();
+    [up, right, down, left]
+}
+
+/// Castles from a game played for a few turns, taking the first placement each time.
+#[cfg(test)]
+pub(crate) fn test_castles() -> Vec<Castle> {
+    use crate::game::{test_game, PlayerSecret};
+    use rand::{rngs::StdRng, SeedableRng};
+    let players: Vec<PlayerSecret> = vec!["a".into(), "b".into()];
+    let mut game = test_game(&["a", "b"]);
+    let mut rng = StdRng::seed_from_u64(0);
+    for _ in 0..12 {
+        let next = players.iter().find_map(|player| {
+            let action = game
+                .possible_actions(player)
+                .into_iter()
+                .find(|a| matches!(a, disastle_castle_rust::Action::Place(..)))?;
+            game.action_with_rng(player, game.get_action_seq(), action, &mut rng)
+                .ok()
+        });
+        match next {
+            Some(next) => game = next,
+            None => break,
+        }
+    }
+    game.player_view(&players[0]).castles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_game;
+    #[test]
+    fn test_room_access() {
+        for castle in test_game(&["a", "b"]).player_view(&"a".into()).castles {
+            let thrones = castle.throne_positions();
+            assert_eq!(thrones.len(), 1);
+            assert!(castle.contains(thrones[0]));
+            assert!(castle.room_at(thrones[0]).unwrap().throne);
+            assert!(!castle.contains(thrones[0].up()));
+            assert_eq!(castle.iter().count(), castle.rooms.len());
+        }
+    }
+}
@@ -0,0 +1,136 @@
+use super::{connections, is_link, CastleExt};
+use crate::pos::{Direction, Pos};
+use disastle_castle_rust::{Castle, Connection};
+
+/// Whether a connection is gold, the marker that must be linked for its room to be powered.
+pub fn is_gold(connection: &Connection) -> bool {
+    matches!(
+        connection,
+        Connection::Diamond(true) | Connection::Cross(true) | Connection::Moon(true)
+    )
+}
+
+/// Whether the side of the room at `pos` facing `direction` forms a link with the room on
+/// that side. `None` if there is no room at `pos`.
+pub fn is_linked(castle: &Castle, pos: Pos, direction: Direction) -> Option<bool> {
+    let own = &connections(castle.room_at(pos)?)[direction.index()];
+    Some(match castle.room_at(pos.step(direction)) {
+        Some(other) => is_link(own, &connections(other)[direction.opposite().index()]),
+        None => false,
+    })
+}
+
+/// `None` when the side is not gold or there is no room at `pos`, otherwise whether the
+/// gold connection is linked.
+pub fn powered_side(castle: &Castle, pos: Pos, direction: Direction) -> Option<bool> {
+    let room = castle.room_at(pos)?;
+    if is_gold(&connections(room)[direction.index()]) {
+        is_linked(castle, pos, direction)
+    } else {
+        None
+    }
+}
+
+pub fn is_powered(castle: &Castle, pos: Pos) -> bool {
+    let sides: Vec<Option<bool>> = Direction::ALL
+        .iter()
+        .map(|d| powered_side(castle, pos, *d))
+        .collect();
+    sides.iter().any(Option::is_some) && sides.iter().all(|side| side.unwrap_or(true))
+}
+
+pub fn powered_links(castle: &Castle) -> Vec<(Pos, Pos)> {
+    let mut links = Vec::new();
+    for (pos, room) in castle.iter() {
+        let own = connections(room);
+        for &i in &[0, 1] {
+            let neighbor = pos.neighbors()[i];
+            if let Some(other) = castle.room_at(neighbor) {
+                let facing = &connections(other)[(i + 2) % 4];
+                if is_link(&own[i], facing) && (is_gold(&own[i]) || is_gold(facing)) {
+                    links.push((pos, neighbor));
+                }
+            }
+        }
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{castle::test_castles, load_rooms_from_str};
+    use disastle_castle_rust::Room;
+
+    fn room_with(side: Direction, connection: Connection) -> Room {
+        let mut room = load_rooms_from_str(include_str!("../../rooms.ron"))
+            .unwrap()
+            .remove(0);
+        let mut sides = [
+            Connection::None,
+            Connection::None,
+            Connection::None,
+            Connection::None,
+        ];
+        sides[side.index()] = connection;
+        let [up, right, down, left] = sides;
+        room.connections = (up, right, down, left);
+        room
+    }
+
+    #[test]
+    fn test_powered_side_matrix() {
+        let base = test_castles().remove(0);
+        let center = Pos::new(10, 10);
+        for &direction in Direction::ALL.iter() {
+            for &gold in &[false, true] {
+                let facing = [
+                    (None, false),
+                    (Some(Connection::Moon(false)), true),
+                    (Some(Connection::Moon(true)), true),
+                    (Some(Connection::Wild), true),
+                    (Some(Connection::Cross(false)), false),
+                ];
+                for (neighbor, linked) in facing.iter().cloned() {
+                    let mut castle = base.clone();
+                    castle
+                        .rooms
+                        .insert(center.into(), room_with(direction, Connection::Moon(gold)));
+                    if let Some(connection) = neighbor {
+                        castle.rooms.insert(
+                            center.step(direction).into(),
+                            room_with(direction.opposite(), connection),
+                        );
+                    }
+                    assert_eq!(is_linked(&castle, center, direction), Some(linked));
+                    let expected = if gold { Some(linked) } else { None };
+                    for &other in Direction::ALL.iter() {
+                        let side = powered_side(&castle, center, other);
+                        assert_eq!(side, if other == direction { expected } else { None });
+                    }
+                    assert_eq!(is_powered(&castle, center), gold && linked);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_powered_rooms_use_powered_links() {
+        for castle in test_castles() {
+            let links = castle.powered_links();
+            for pos in castle.powered_rooms() {
+                for &direction in Direction::ALL.iter() {
+                    if powered_side(&castle, pos, direction).is_some() {
+                        let neighbor = pos.step(direction);
+                        let pair = if direction.index() < 2 {
+                            (pos, neighbor)
+                        } else {
+                            (neighbor, pos)
+                        };
+                        assert!(links.contains(&pair));
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use super::{connections, is_link, CastleExt};
+use crate::pos::Pos;
+use disastle_castle_rust::Castle;
+
+struct Tarjan {
+    adjacency: Vec<Vec<usize>>,
+    discovered: Vec<Option<usize>>,
+    low: Vec<usize>,
+    cut: Vec<bool>,
+    time: usize,
+}
+
+impl Tarjan {
+    fn visit(&mut self, node: usize, parent: Option<usize>) {
+        self.discovered[node] = Some(self.time);
+        self.low[node] = self.time;
+        self.time += 1;
+        let mut children = 0;
+        for i in 0..self.adjacency[node].len() {
+            let next = self.adjacency[node][i];
+            match self.discovered[next] {
+                None => {
+                    children += 1;
+                    self.visit(next, Some(node));
+                    self.low[node] = self.low[node].min(self.low[next]);
+                    if parent.is_some() && self.low[next] >= self.discovered[node].unwrap() {
+                        self.cut[node] = true;
+                    }
+                }
+                Some(time) if Some(next) != parent => {
+                    self.low[node] = self.low[node].min(time);
+                }
+                Some(_) => {}
+            }
+        }
+        if parent.is_none() && children > 1 {
+            self.cut[node] = true;
+        }
+    }
+}
+
+/// Non-throne rooms whose removal leaves every other room linked to a throne: the rooms
+/// that are not articulation points of the link graph, found in one depth-first pass from a
+/// virtual root joined to every throne.
+pub fn removable_rooms(castle: &Castle) -> Vec<Pos> {
+    let positions: Vec<Pos> = castle.iter().map(|(pos, _)| pos).collect();
+    let root = positions.len();
+    let mut adjacency = vec![Vec::new(); root + 1];
+    for (node, &pos) in positions.iter().enumerate() {
+        let room = castle.room_at(pos).unwrap();
+        if room.throne {
+            adjacency[root].push(node);
+            adjacency[node].push(root);
+        }
+        let own = connections(room);
+        for (i, neighbor) in pos.neighbors().iter().enumerate() {
+            if let Some(other) = castle.room_at(*neighbor) {
+                if is_link(&own[i], &connections(other)[(i + 2) % 4]) {
+                    adjacency[node].push(positions.binary_search(neighbor).unwrap());
+                }
+            }
+        }
+    }
+    let mut tarjan = Tarjan {
+        adjacency,
+        discovered: vec![None; root + 1],
+        low: vec![0; root + 1],
+        cut: vec![false; root + 1],
+        time: 0,
+    };
+    tarjan.visit(root, None);
+    positions
+        .into_iter()
+        .enumerate()
+        .filter(|&(node, pos)| !tarjan.cut[node] && !castle.room_at(pos).unwrap().throne)
+        .map(|(_, pos)| pos)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::castle::{test_castles, CastleExt};
+
+    #[test]
+    fn test_removable_matches_brute_force() {
+        for castle in test_castles() {
+            let brute: Vec<_> = castle
+                .iter()
+                .filter(|(_, room)| !room.throne)
+                .filter(|(pos, _)| {
+                    let mut removed = castle.clone();
+                    removed.rooms.remove(&(*pos).into());
+                    removed.validate().is_ok()
+                })
+                .map(|(pos, _)| pos)
+                .collect();
+            assert_eq!(castle.removable_rooms(), brute);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::{connections, power::is_powered, CastleExt};
+use crate::pos::Pos;
+use disastle_castle_rust::{Castle, Connection, Room};
+
+const CELL_WIDTH: usize = 7;
+const NAME_WIDTH: usize = CELL_WIDTH - 2;
+
+/// Key to the symbols of `CastleExt::render_ascii`, for printing alongside drawings.
+pub const ASCII_LEGEND: &str = "D C M gold diamond, cross, moon; d c m plain ones; * wild; \
+                                 # throne corners; == powered room";
+
+fn symbol(connection: &Connection) -> Option<char> {
+    match connection {
+        Connection::None => None,
+        Connection::Diamond(gold) => Some(if *gold { 'D' } else { 'd' }),
+        Connection::Cross(gold) => Some(if *gold { 'C' } else { 'c' }),
+        Connection::Moon(gold) => Some(if *gold { 'M' } else { 'm' }),
+        Connection::Wild => Some('*'),
+    }
+}
+
+fn cell(room: &Room, powered: bool) -> [String; 3] {
+    let [up, right, down, left] = connections(room);
+    let corner = if room.throne { '#' } else { '+' };
+    let line = if powered { "==" } else { "--" };
+    let edge = |connection: &Connection| {
+        format!(
+            "{}{}{}{}{}",
+            corner,
+            line,
+            symbol(connection).unwrap_or(line.chars().next().unwrap()),
+            line,
+            corner
+        )
+    };
+    let name: String = room.name.chars().take(NAME_WIDTH).collect();
+    [
+        edge(&up),
+        format!(
+            "{}{:^width$}{}",
+            symbol(&left).unwrap_or('|'),
+            name,
+            symbol(&right).unwrap_or('|'),
+            width = NAME_WIDTH
+        ),
+        edge(&down),
+    ]
+}
+
+pub fn ascii(castle: &Castle) -> String {
+    let bounds = match castle.bounds() {
+        Some(bounds) => bounds,
+        None => return String::new(),
+    };
+    let mut lines = Vec::new();
+    for y in (bounds.min.y..=bounds.max.y).rev() {
+        let mut row = [String::new(), String::new(), String::new()];
+        for x in bounds.min.x..=bounds.max.x {
+            let pos = Pos::new(x, y);
+            match castle.room_at(pos) {
+                Some(room) => {
+                    let cell = cell(room, is_powered(castle, pos));
+                    for (line, part) in row.iter_mut().zip(cell.iter()) {
+                        line.push_str(part);
+                    }
+                }
+                None => {
+                    for line in row.iter_mut() {
+                        line.push_str(&" ".repeat(CELL_WIDTH));
+                    }
+                }
+            }
+        }
+        lines.extend(row.iter().map(|line| line.trim_end().to_string()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::castle::power::is_powered;
+    use crate::{
+        castle::{test_castles, CastleExt},
+        load_thrones_from_str,
+    };
+    use disastle_castle_rust::Castle;
+    #[test]
+    fn test_render_throne() {
+        let throne = load_thrones_from_str(include_str!("../../thrones.ron"))
+            .unwrap()
+            .remove(0);
+        let drawing = Castle::new(throne.clone()).render_ascii();
+        let lines: Vec<&str> = drawing.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].starts_with('#') && lines[2].starts_with('#'));
+        let name: String = throne.name.chars().take(5).collect();
+        assert!(lines[1].contains(&name));
+    }
+    #[test]
+    fn test_render_castle() {
+        for castle in test_castles() {
+            let drawing = castle.render_ascii();
+            let lines: Vec<&str> = drawing.lines().collect();
+            let bounds = castle.bounds().unwrap();
+            assert_eq!(lines.len(), 3 * bounds.height() as usize);
+            for (pos, room) in castle.iter() {
+                let row = 3 * (bounds.max.y - pos.y) as usize;
+                let column = 7 * (pos.x - bounds.min.x) as usize;
+                let top: String = lines[row].chars().skip(column).take(7).collect();
+                let corner = if room.throne { '#' } else { '+' };
+                assert!(top.starts_with(corner) && top.ends_with(corner));
+                assert_eq!(&top[1..3] == "==", is_powered(&castle, pos));
+                let middle: String = lines[row + 1].chars().skip(column).take(7).collect();
+                let name: String = room.name.chars().take(5).collect();
+                assert!(middle.contains(name.trim()));
+            }
+        }
+    }
+}
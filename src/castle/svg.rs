@@ -0,0 +1,234 @@
+use super::{connections, is_gold, is_link, CastleExt};
+use crate::pos::Pos;
+use disastle_castle_rust::{Castle, Connection, Room};
+use std::fmt::Write;
+
+const CELL: i32 = 60;
+const MARGIN: i32 = 10;
+const LABEL: i32 = 20;
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn document(width: i32, height: i32, body: &str) -> String {
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" font-family=\"sans-serif\">\n{}</svg>\n",
+        body,
+        w = width,
+        h = height
+    )
+}
+
+fn color(connection: &Connection) -> &'static str {
+    match connection {
+        Connection::None => "none",
+        Connection::Diamond(_) => "#3b7dd8",
+        Connection::Cross(_) => "#d83b3b",
+        Connection::Moon(_) => "#8a3bd8",
+        Connection::Wild => "#d8a23b",
+    }
+}
+
+/// Marker for a connection centered on `(cx, cy)`, filled when gold.
+fn marker(connection: &Connection, cx: i32, cy: i32) -> String {
+    let color = color(connection);
+    let fill = if is_gold(connection) { color } else { "white" };
+    match connection {
+        Connection::None => String::new(),
+        Connection::Diamond(_) => format!(
+            "<polygon points=\"{},{} {},{} {},{} {},{}\" fill=\"{}\" stroke=\"{}\"/>\n",
+            cx,
+            cy - 6,
+            cx + 6,
+            cy,
+            cx,
+            cy + 6,
+            cx - 6,
+            cy,
+            fill,
+            color
+        ),
+        Connection::Cross(gold) => format!(
+            "<path d=\"M{} {}H{}M{} {}V{}\" stroke=\"{}\" stroke-width=\"{}\"/>\n",
+            cx - 6,
+            cy,
+            cx + 6,
+            cx,
+            cy - 6,
+            cy + 6,
+            color,
+            if *gold { 4 } else { 2 }
+        ),
+        Connection::Moon(_) => format!(
+            "<circle cx=\"{}\" cy=\"{}\" r=\"6\" fill=\"{}\" stroke=\"{}\"/>\n",
+            cx, cy, fill, color
+        ),
+        Connection::Wild => format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"10\" height=\"10\" fill=\"{}\" stroke=\"{}\"/>\n",
+            cx - 5,
+            cy - 5,
+            color,
+            color
+        ),
+    }
+}
+
+fn room(room: &Room, x: i32, y: i32) -> String {
+    let fill = if room.throne { "#e8c872" } else { "#f4ecd8" };
+    let mut svg = format!(
+        "<g class=\"room\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" stroke=\"black\"/>\n",
+        x, y, CELL, CELL, fill
+    );
+    let _ = writeln!(
+        svg,
+        "<text x=\"{}\" y=\"{}\" font-size=\"8\" text-anchor=\"middle\">{}</text>",
+        x + CELL / 2,
+        y + CELL / 2 + 3,
+        escape(&room.name)
+    );
+    let [up, right, down, left] = connections(room);
+    let half = CELL / 2;
+    svg += &marker(&up, x + half, y + 8);
+    svg += &marker(&right, x + CELL - 8, y + half);
+    svg += &marker(&down, x + half, y + CELL - 8);
+    svg += &marker(&left, x + 8, y + half);
+    svg += "</g>\n";
+    svg
+}
+
+/// The castle drawn at `(x, y)` with the highest row on top, and its width and height.
+fn castle_at(castle: &Castle, x: i32, y: i32) -> (String, i32, i32) {
+    let bounds = match castle.bounds() {
+        Some(bounds) => bounds,
+        None => return (String::new(), 0, 0),
+    };
+    let origin = |pos: Pos| {
+        (
+            x + (pos.x - bounds.min.x) * CELL,
+            y + (bounds.max.y - pos.y) * CELL,
+        )
+    };
+    let mut svg = String::new();
+    for (pos, r) in castle.iter() {
+        let (rx, ry) = origin(pos);
+        svg += &room(r, rx, ry);
+        // Links are drawn once, from the lower or left room of each adjacent pair
+        let [up, right, _, _] = connections(r);
+        for (neighbor, own, facing_index) in [(pos.up(), up, 2), (pos.right(), right, 3)] {
+            if let Some(other) = castle.room_at(neighbor) {
+                let facing = connections(other)[facing_index].clone();
+                if !is_link(&own, &facing) {
+                    continue;
+                }
+                let (nx, ny) = origin(neighbor);
+                let powered = is_gold(&own) || is_gold(&facing);
+                let _ = writeln!(
+                    svg,
+                    "<line class=\"link{}\" x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\"/>",
+                    if powered { " powered" } else { "" },
+                    rx + CELL / 2,
+                    ry + CELL / 2,
+                    nx + CELL / 2,
+                    ny + CELL / 2,
+                    color(&own),
+                    if powered { 4 } else { 1 }
+                );
+            }
+        }
+    }
+    (svg, bounds.width() * CELL, bounds.height() * CELL)
+}
+
+/// Standalone SVG document of a single castle.
+pub fn castle(castle: &Castle) -> String {
+    let (body, width, height) = castle_at(castle, MARGIN, MARGIN);
+    document(width + 2 * MARGIN, height + 2 * MARGIN, &body)
+}
+
+/// SVG document of the whole table: every castle by seat, stacked vertically, and the shop
+/// along the bottom.
+pub fn table(castles: &[Castle], shop: &[Room]) -> String {
+    let mut body = String::new();
+    let mut y = MARGIN;
+    let mut width = 0;
+    for (seat, c) in castles.iter().enumerate() {
+        let _ = writeln!(
+            body,
+            "<text x=\"{}\" y=\"{}\" font-size=\"14\">Player {}: treasure {}, damage {}</text>",
+            MARGIN,
+            y + 14,
+            seat + 1,
+            c.get_treasure(),
+            c.damage
+        );
+        let (svg, w, h) = castle_at(c, MARGIN, y + LABEL);
+        body += &svg;
+        width = width.max(w);
+        y += LABEL + h + MARGIN;
+    }
+    let _ = writeln!(
+        body,
+        "<text x=\"{}\" y=\"{}\" font-size=\"14\">Shop</text>",
+        MARGIN,
+        y + 14
+    );
+    for (i, r) in shop.iter().enumerate() {
+        body += &room(r, MARGIN + i as i32 * (CELL + MARGIN), y + LABEL);
+    }
+    width = width.max(shop.len() as i32 * (CELL + MARGIN));
+    y += LABEL + CELL + MARGIN;
+    document(width + 2 * MARGIN, y, &body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{escape, table};
+    use crate::castle::power::{is_linked, powered_links};
+    use crate::castle::{test_castles, CastleExt};
+    use crate::game::test_game;
+    use crate::pos::Direction;
+    #[test]
+    fn test_render_table() {
+        let game = test_game(&["a", "b"]);
+        let view = game.player_view(&"a".into());
+        let svg = view.render_svg();
+        assert!(svg.starts_with("<svg"));
+        let rooms = view.castles.iter().map(|c| c.rooms.len()).sum::<usize>() + view.shop.len();
+        assert_eq!(svg.matches("class=\"room\"").count(), rooms);
+        assert!(view.castles[0].render_svg().starts_with("<svg"));
+    }
+    #[test]
+    fn test_render_links() {
+        let castles = test_castles();
+        let mut drawn = 0;
+        for castle in &castles {
+            let svg = castle.render_svg();
+            let links = castle
+                .iter()
+                .flat_map(|(pos, _)| {
+                    [Direction::Up, Direction::Right]
+                        .map(|direction| is_linked(castle, pos, direction))
+                })
+                .filter(|linked| *linked == Some(true))
+                .count();
+            assert_eq!(svg.matches("class=\"link").count(), links);
+            let powered = powered_links(castle).len();
+            assert_eq!(svg.matches("class=\"link powered\"").count(), powered);
+            drawn += links;
+        }
+        assert!(drawn > 0);
+        let svg = table(&castles, &[]);
+        assert_eq!(svg.matches("class=\"link").count(), drawn);
+    }
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<Hall & \"Keep\">"),
+            "&lt;Hall &amp; &quot;Keep&quot;&gt;"
+        );
+    }
+}
@@ -0,0 +1,90 @@
+use super::{connections, from_rooms, is_link, BuildError, CastleExt};
+use crate::{disaster::Target, pos::Pos};
+use disastle_castle_rust::{Castle, Room};
+use rand::{seq::SliceRandom, Rng};
+use std::collections::{BTreeMap, VecDeque};
+
+/// Number of links between each room and the nearest throne, found breadth-first from every
+/// throne at once. Rooms not linked to a throne are left out.
+pub fn link_distances(castle: &Castle) -> BTreeMap<Pos, u32> {
+    let mut distances = BTreeMap::new();
+    let mut queue = VecDeque::new();
+    for pos in castle.throne_positions() {
+        distances.insert(pos, 0);
+        queue.push_back(pos);
+    }
+    while let Some(pos) = queue.pop_front() {
+        let own = connections(castle.room_at(pos).unwrap());
+        for (i, neighbor) in pos.neighbors().iter().enumerate() {
+            let room = match castle.room_at(*neighbor) {
+                Some(room) if !distances.contains_key(neighbor) => room,
+                _ => continue,
+            };
+            if is_link(&own[i], &connections(room)[(i + 2) % 4]) {
+                distances.insert(*neighbor, distances[&pos] + 1);
+                queue.push_back(*neighbor);
+            }
+        }
+    }
+    distances
+}
+
+/// Rooms `target` may destroy: all of them for a region, the candidates for a random pick.
+pub fn targeted_rooms(castle: &Castle, target: &Target) -> Vec<Pos> {
+    match target {
+        Target::BeyondDistance(steps) => link_distances(castle)
+            .into_iter()
+            .filter(|&(_, distance)| distance > *steps as u32)
+            .map(|(pos, _)| pos)
+            .collect(),
+        Target::RandomOuterRoom => castle.removable_rooms(),
+    }
+}
+
+/// Destroy the rooms hit by `target`, returning the rebuilt castle and the destroyed rooms.
+/// Rooms beyond a distance are only ever linked to the throne through rooms closer to it, so
+/// the rest of the castle stays linked.
+pub fn strike<R: Rng + ?Sized>(
+    castle: &Castle,
+    target: &Target,
+    rng: &mut R,
+) -> Result<(Castle, Vec<Room>), BuildError> {
+    let mut hit = targeted_rooms(castle, target);
+    if let Target::RandomOuterRoom = target {
+        hit = hit.choose(rng).into_iter().cloned().collect();
+    }
+    let mut rooms = BTreeMap::new();
+    let mut destroyed = Vec::new();
+    for (pos, room) in castle.iter() {
+        if hit.contains(&pos) {
+            destroyed.push(room.clone());
+        } else {
+            rooms.insert(pos, room.clone());
+        }
+    }
+    let mut rest = from_rooms(rooms)?;
+    rest.damage = castle.damage;
+    log_debug!(strike = ?target, destroyed = destroyed.len(), "castle struck");
+    Ok((rest, destroyed))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::castle::{test_castles, CastleExt};
+    use crate::disaster::Target;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_strike_beyond_distance() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for castle in test_castles() {
+            let target = Target::BeyondDistance(1);
+            let hit = castle.targeted_rooms(&target);
+            let (rest, destroyed) = castle.strike(&target, &mut rng).unwrap();
+            assert_eq!(destroyed.len(), hit.len());
+            assert_eq!(rest.rooms.len() + hit.len(), castle.rooms.len());
+            assert!(rest.link_distances().values().all(|&d| d <= 1));
+            assert!(rest.validate().is_ok());
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use super::{connections, CastleExt};
+use crate::pos::Pos;
+use disastle_castle_rust::{Castle, Connection};
+use std::collections::{BTreeSet, VecDeque};
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ValidationError {
+    #[error("The castle has no throne")]
+    NoThrone,
+    #[error("Room at {0} is not linked to a throne")]
+    Disconnected(Pos),
+    #[error("Room at {pos} has a connection that does not match the room at {neighbor}")]
+    Mismatch { pos: Pos, neighbor: Pos },
+}
+
+/// Whether two facing connections may touch: blank against blank, wild against any
+/// connection, or two connections of the same suit.
+pub fn compatible(a: &Connection, b: &Connection) -> bool {
+    match (a, b) {
+        (Connection::None, Connection::None) => true,
+        (Connection::None, _) | (_, Connection::None) => false,
+        (Connection::Wild, _) | (_, Connection::Wild) => true,
+        (Connection::Diamond(_), Connection::Diamond(_))
+        | (Connection::Cross(_), Connection::Cross(_))
+        | (Connection::Moon(_), Connection::Moon(_)) => true,
+        _ => false,
+    }
+}
+
+/// Whether two facing connections form a link.
+pub fn is_link(a: &Connection, b: &Connection) -> bool {
+    *a != Connection::None && compatible(a, b)
+}
+
+pub fn validate(castle: &Castle) -> Result<(), Vec<ValidationError>> {
+    let mut errors = Vec::new();
+    for (pos, room) in castle.iter() {
+        let own = connections(room);
+        // Each pair is checked once, from the room below or to the left
+        for &i in &[0, 1] {
+            let neighbor = pos.neighbors()[i];
+            if let Some(other) = castle.room_at(neighbor) {
+                if !compatible(&own[i], &connections(other)[(i + 2) % 4]) {
+                    errors.push(ValidationError::Mismatch { pos, neighbor });
+                }
+            }
+        }
+    }
+    let thrones = castle.throne_positions();
+    if thrones.is_empty() {
+        errors.push(ValidationError::NoThrone);
+    }
+    let mut reached: BTreeSet<Pos> = thrones.iter().copied().collect();
+    let mut queue: VecDeque<Pos> = thrones.into_iter().collect();
+    while let Some(pos) = queue.pop_front() {
+        let own = connections(castle.room_at(pos).unwrap());
+        for (i, neighbor) in pos.neighbors().iter().enumerate() {
+            if let Some(other) = castle.room_at(*neighbor) {
+                if !reached.contains(neighbor) && is_link(&own[i], &connections(other)[(i + 2) % 4])
+                {
+                    reached.insert(*neighbor);
+                    queue.push_back(*neighbor);
+                }
+            }
+        }
+    }
+    errors.extend(
+        castle
+            .iter()
+            .filter(|(pos, _)| !reached.contains(pos))
+            .map(|(pos, _)| ValidationError::Disconnected(pos)),
+    );
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::test_game;
+    #[test]
+    fn test_compatible() {
+        use Connection::*;
+        assert!(compatible(&None, &None));
+        assert!(!compatible(&Moon(false), &None));
+        assert!(compatible(&Moon(true), &Moon(false)));
+        assert!(compatible(&Wild, &Cross(false)));
+        assert!(!compatible(&Diamond(false), &Cross(false)));
+        assert!(!is_link(&None, &None));
+    }
+    #[test]
+    fn test_validate() {
+        for castle in test_game(&["a", "b"]).player_view(&"a".into()).castles {
+            assert_eq!(castle.validate(), Ok(()));
+            let mut broken = castle.clone();
+            broken.rooms.clear();
+            assert_eq!(broken.validate(), Err(vec![ValidationError::NoThrone]));
+        }
+    }
+}
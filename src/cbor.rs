@@ -0,0 +1,50 @@
+//! CBOR encoding of player views and actions for constrained clients that would rather not
+//! parse JSON.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::io;
+
+pub type EncodeError = ciborium::ser::Error<io::Error>;
+pub type DecodeError = ciborium::de::Error<io::Error>;
+
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut bytes = Vec::new();
+    ciborium::ser::into_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    ciborium::de::from_reader(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::game::{test_game, PlayerSecret, PlayerView};
+    use disastle_castle_rust::Action;
+
+    #[test]
+    fn test_player_view_cross_format() {
+        let game = test_game(&["a", "b", "c"]);
+        let view = game.player_view(&PlayerSecret::from("b"));
+        let from_cbor: PlayerView = decode(&encode(&view).unwrap()).unwrap();
+        let from_json: PlayerView =
+            serde_json::from_str(&serde_json::to_string(&from_cbor).unwrap()).unwrap();
+        assert_eq!(from_json, view);
+        assert_eq!(encode(&from_json).unwrap(), encode(&view).unwrap());
+    }
+    #[test]
+    fn test_actions_cross_format() {
+        let game = test_game(&["a", "b"]);
+        let actions: Vec<Action> = ["a", "b"]
+            .iter()
+            .flat_map(|&s| game.possible_actions(&PlayerSecret::from(s)))
+            .collect();
+        assert!(!actions.is_empty());
+        let from_cbor: Vec<Action> = decode(&encode(&actions).unwrap()).unwrap();
+        let from_json: Vec<Action> =
+            serde_json::from_str(&serde_json::to_string(&actions).unwrap()).unwrap();
+        assert_eq!(from_cbor, actions);
+        assert_eq!(from_json, actions);
+    }
+}
@@ -0,0 +1,420 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::Peekable,
+    str::CharIndices,
+};
+use thiserror::Error;
+
+/// Deepest nesting of parentheses, negations and function calls a formula may use, so a
+/// hostile card cannot overflow the stack of the recursive parser.
+const MAX_DEPTH: usize = 64;
+
+/// Most operands and operators a formula may hold. Long chains such as `1+1+...+1` nest
+/// one level per operator once parsed, so they are bounded too for evaluating and dropping.
+const MAX_NODES: usize = 256;
+
+/// What a damage formula may refer to when a disaster strikes a castle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct DamageContext {
+    /// `prev`: disasters resolved before this one.
+    pub previous_disasters: u8,
+    /// `rooms`: rooms in the castle struck, throne included.
+    pub rooms: u8,
+    /// `round`
+    pub round: u8,
+    /// `players`: castles in the game, lost ones included.
+    pub players: u8,
+}
+
+impl DamageContext {
+    /// Context knowing nothing but the number of previous disasters.
+    pub fn after(previous_disasters: u8) -> DamageContext {
+        DamageContext {
+            previous_disasters,
+            ..DamageContext::default()
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FormulaError {
+    #[error("Unexpected '{found}' at {index}")]
+    UnexpectedChar { index: usize, found: char },
+    #[error("Unexpected end of formula")]
+    UnexpectedEnd,
+    #[error("Unknown variable {0}")]
+    UnknownVariable(String),
+    #[error("Unknown function {0}")]
+    UnknownFunction(String),
+    #[error("{name} takes {expected} arguments but {found} were given")]
+    WrongArity {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+    #[error("Formula nests more than {} levels deep", MAX_DEPTH)]
+    TooDeep,
+    #[error("Formula has more than {} operands and operators", MAX_NODES)]
+    TooLong,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Variable {
+    Previous,
+    Rooms,
+    Round,
+    Players,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Max,
+    Min,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Expr {
+    Number(i64),
+    Variable(Variable),
+    Negate(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn evaluate(&self, context: &DamageContext) -> i64 {
+        match self {
+            Expr::Number(n) => *n,
+            Expr::Variable(Variable::Previous) => context.previous_disasters as i64,
+            Expr::Variable(Variable::Rooms) => context.rooms as i64,
+            Expr::Variable(Variable::Round) => context.round as i64,
+            Expr::Variable(Variable::Players) => context.players as i64,
+            Expr::Negate(e) => e.evaluate(context).saturating_neg(),
+            Expr::Binary(op, a, b) => {
+                let (a, b) = (a.evaluate(context), b.evaluate(context));
+                match op {
+                    Op::Add => a.saturating_add(b),
+                    Op::Sub => a.saturating_sub(b),
+                    Op::Mul => a.saturating_mul(b),
+                    Op::Div => a.checked_div(b).unwrap_or(0),
+                    Op::Max => a.max(b),
+                    Op::Min => a.min(b),
+                }
+            }
+        }
+    }
+}
+
+/// Recursive descent over `expr = term (('+' | '-') term)*`,
+/// `term = factor (('*' | '/') factor)*` and
+/// `factor = number | variable | function '(' expr ',' expr ')' | '(' expr ')' | '-' factor`.
+struct Parser<'a> {
+    source: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    depth: usize,
+    nodes: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !c.is_whitespace() {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().cloned()
+    }
+    fn expect(&mut self, expected: char) -> Result<(), FormulaError> {
+        match self.peek() {
+            Some((_, c)) if c == expected => {
+                self.chars.next();
+                Ok(())
+            }
+            Some((index, found)) => Err(FormulaError::UnexpectedChar { index, found }),
+            None => Err(FormulaError::UnexpectedEnd),
+        }
+    }
+    /// Count one more node of the tree against `MAX_NODES`.
+    fn node(&mut self) -> Result<(), FormulaError> {
+        self.nodes += 1;
+        if self.nodes > MAX_NODES {
+            return Err(FormulaError::TooLong);
+        }
+        Ok(())
+    }
+    fn binary(&mut self, op: Op, a: Expr, b: Expr) -> Result<Expr, FormulaError> {
+        self.node()?;
+        Ok(Expr::Binary(op, Box::new(a), Box::new(b)))
+    }
+    fn expr(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.term()?;
+        while let Some((_, c)) = self.peek() {
+            let op = match c {
+                '+' => Op::Add,
+                '-' => Op::Sub,
+                _ => break,
+            };
+            self.chars.next();
+            let rhs = self.term()?;
+            expr = self.binary(op, expr, rhs)?;
+        }
+        Ok(expr)
+    }
+    fn term(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.factor()?;
+        while let Some((_, c)) = self.peek() {
+            let op = match c {
+                '*' => Op::Mul,
+                '/' => Op::Div,
+                _ => break,
+            };
+            self.chars.next();
+            let rhs = self.factor()?;
+            expr = self.binary(op, expr, rhs)?;
+        }
+        Ok(expr)
+    }
+    fn factor(&mut self) -> Result<Expr, FormulaError> {
+        if self.depth == MAX_DEPTH {
+            return Err(FormulaError::TooDeep);
+        }
+        self.node()?;
+        self.depth += 1;
+        let factor = self.nested_factor();
+        self.depth -= 1;
+        factor
+    }
+    fn nested_factor(&mut self) -> Result<Expr, FormulaError> {
+        match self.peek() {
+            None => Err(FormulaError::UnexpectedEnd),
+            Some((_, '-')) => {
+                self.chars.next();
+                Ok(Expr::Negate(Box::new(self.factor()?)))
+            }
+            Some((_, '(')) => {
+                self.chars.next();
+                let expr = self.expr()?;
+                self.expect(')')?;
+                Ok(expr)
+            }
+            Some((start, c)) if c.is_ascii_digit() => {
+                let end = self.take_while(|c| c.is_ascii_digit());
+                let number = self.source[start..end].parse().unwrap_or(i64::MAX);
+                Ok(Expr::Number(number))
+            }
+            Some((start, c)) if c.is_ascii_alphabetic() => {
+                let end = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                let source = self.source;
+                self.named(&source[start..end])
+            }
+            Some((index, found)) => Err(FormulaError::UnexpectedChar { index, found }),
+        }
+    }
+    fn named(&mut self, name: &str) -> Result<Expr, FormulaError> {
+        let variable = match name {
+            "prev" => Some(Variable::Previous),
+            "rooms" => Some(Variable::Rooms),
+            "round" => Some(Variable::Round),
+            "players" => Some(Variable::Players),
+            _ => None,
+        };
+        if let Some(variable) = variable {
+            return Ok(Expr::Variable(variable));
+        }
+        let op = match name {
+            "max" => Op::Max,
+            "min" => Op::Min,
+            _ if matches!(self.peek(), Some((_, '('))) => {
+                return Err(FormulaError::UnknownFunction(name.to_string()))
+            }
+            _ => return Err(FormulaError::UnknownVariable(name.to_string())),
+        };
+        self.expect('(')?;
+        let mut args = vec![self.expr()?];
+        while let Some((_, ',')) = self.peek() {
+            self.chars.next();
+            args.push(self.expr()?);
+        }
+        self.expect(')')?;
+        if args.len() != 2 {
+            return Err(FormulaError::WrongArity {
+                name: name.to_string(),
+                expected: 2,
+                found: args.len(),
+            });
+        }
+        let b = args.pop().unwrap();
+        let a = args.pop().unwrap();
+        self.binary(op, a, b)
+    }
+    /// Consume characters matching `f`, returning the index after the last one.
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> usize {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if !f(c) {
+                break;
+            }
+            self.chars.next();
+        }
+        self.chars.peek().map_or(self.source.len(), |&(i, _)| i)
+    }
+}
+
+/// Damage expression such as `2*prev + max(0, rooms-5)`, parsed once when card data is
+/// loaded. Supports integers, `+ - * /`, parentheses, `max`, `min` and the variables of
+/// `DamageContext`. Results below zero deal no damage.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Formula {
+    // Boxed to keep disasters small, as they are held by value in decks and events
+    source: Box<str>,
+    expr: Box<Expr>,
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Formula, FormulaError> {
+        let mut parser = Parser {
+            source,
+            chars: source.char_indices().peekable(),
+            depth: 0,
+            nodes: 0,
+        };
+        let expr = parser.expr()?;
+        match parser.peek() {
+            Some((index, found)) => Err(FormulaError::UnexpectedChar { index, found }),
+            None => Ok(Formula {
+                source: source.into(),
+                expr: Box::new(expr),
+            }),
+        }
+    }
+    pub fn evaluate(&self, context: &DamageContext) -> u8 {
+        self.expr.evaluate(context).max(0).min(u8::MAX as i64) as u8
+    }
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+impl TryFrom<String> for Formula {
+    type Error = FormulaError;
+    fn try_from(source: String) -> Result<Formula, FormulaError> {
+        Formula::parse(&source)
+    }
+}
+
+impl From<Formula> for String {
+    fn from(formula: Formula) -> String {
+        formula.source.into()
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+// Formulas are compared by their source so disasters can stay in ordered sets.
+impl PartialEq for Formula {
+    fn eq(&self, other: &Formula) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Formula {}
+
+impl Hash for Formula {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.source.hash(state);
+    }
+}
+
+impl PartialOrd for Formula {
+    fn partial_cmp(&self, other: &Formula) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Formula {
+    fn cmp(&self, other: &Formula) -> Ordering {
+        self.source.cmp(&other.source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DamageContext, Formula, FormulaError, MAX_DEPTH, MAX_NODES};
+
+    #[test]
+    fn test_parse_and_evaluate() {
+        let formula = Formula::parse("2*prev + max(0, rooms-5)").unwrap();
+        let context = DamageContext {
+            previous_disasters: 2,
+            rooms: 8,
+            ..DamageContext::default()
+        };
+        assert_eq!(formula.evaluate(&context), 7);
+        assert_eq!(formula.evaluate(&DamageContext::after(1)), 2);
+        assert_eq!(
+            Formula::parse("-(1 + 2) * 3").unwrap().evaluate(&context),
+            0
+        );
+        assert_eq!(Formula::parse("round / 0").unwrap().evaluate(&context), 0);
+        assert_eq!(
+            Formula::parse("min(players, 3)")
+                .unwrap()
+                .evaluate(&context),
+            0
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Formula::parse("2 * towers"),
+            Err(FormulaError::UnknownVariable("towers".to_string()))
+        );
+        assert_eq!(
+            Formula::parse("abs(prev)"),
+            Err(FormulaError::UnknownFunction("abs".to_string()))
+        );
+        assert_eq!(
+            Formula::parse("max(prev)"),
+            Err(FormulaError::WrongArity {
+                name: "max".to_string(),
+                expected: 2,
+                found: 1,
+            })
+        );
+        assert_eq!(Formula::parse("prev +"), Err(FormulaError::UnexpectedEnd));
+        assert_eq!(
+            Formula::parse("prev )"),
+            Err(FormulaError::UnexpectedChar {
+                index: 5,
+                found: ')'
+            })
+        );
+        let nested = |depth| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+        assert!(Formula::parse(&nested(MAX_DEPTH - 1)).is_ok());
+        assert_eq!(
+            Formula::parse(&nested(MAX_DEPTH)),
+            Err(FormulaError::TooDeep)
+        );
+        assert_eq!(
+            Formula::parse(&"-".repeat(100_000)),
+            Err(FormulaError::TooDeep)
+        );
+        let sum = |terms: usize| vec!["1"; terms].join("+");
+        // Each term and each operator is a node
+        assert!(Formula::parse(&sum(MAX_NODES / 2)).is_ok());
+        assert_eq!(Formula::parse(&sum(100_000)), Err(FormulaError::TooLong));
+    }
+}
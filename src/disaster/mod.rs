@@ -1,23 +1,63 @@
+mod formula;
+
 use serde::{Deserialize, Serialize};
 use std::{fmt, hash::Hash};
 
+pub use formula::{DamageContext, Formula, FormulaError};
+
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct Disaster {
     pub name: String,
     pub diamond: DamageCalculation,
     pub cross: DamageCalculation,
     pub moon: DamageCalculation,
+    /// Catastrophes are always the last disaster of the game and their multipliers count
+    /// every previous disaster twice.
+    #[serde(default)]
+    pub catastrophe: bool,
+    /// Rooms destroyed outright after the damage is dealt.
+    #[serde(default)]
+    pub target: Option<Target>,
+    /// Resolved in order after the damage is dealt.
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    /// Presentation only, never read by game logic.
+    #[serde(default)]
+    pub art: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
 }
 
 impl Disaster {
     pub fn diamond_damage(&self, num_previous_disasters: u8) -> u8 {
-        num_previous_disasters * self.diamond.multiplier + self.diamond.addition
+        self.damage(&DamageContext::after(num_previous_disasters))[0]
     }
     pub fn cross_damage(&self, num_previous_disasters: u8) -> u8 {
-        num_previous_disasters * self.cross.multiplier + self.cross.addition
+        self.damage(&DamageContext::after(num_previous_disasters))[1]
     }
     pub fn moon_damage(&self, num_previous_disasters: u8) -> u8 {
-        num_previous_disasters * self.moon.multiplier + self.moon.addition
+        self.damage(&DamageContext::after(num_previous_disasters))[2]
+    }
+    /// Diamond, cross and moon damage dealt to the castle described by `context`.
+    pub fn damage(&self, context: &DamageContext) -> [u8; 3] {
+        let mut context = *context;
+        if self.catastrophe {
+            context.previous_disasters = context.previous_disasters.saturating_mul(2);
+        }
+        [
+            self.diamond.evaluate(&context),
+            self.cross.evaluate(&context),
+            self.moon.evaluate(&context),
+        ]
+    }
+    /// Damage of every kind combined, for ranking disasters by severity.
+    pub fn total_damage(&self, num_previous_disasters: u8) -> u16 {
+        self.diamond_damage(num_previous_disasters) as u16
+            + self.cross_damage(num_previous_disasters) as u16
+            + self.moon_damage(num_previous_disasters) as u16
     }
 }
 
@@ -25,24 +65,147 @@ impl fmt::Display for Disaster {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Disaster")
             .field("name", &self.name)
-            .field(
-                "diamond",
-                &format!("x{}+{}", &self.diamond.multiplier, &self.diamond.addition),
-            )
-            .field(
-                "cross",
-                &format!("x{}+{}", &self.cross.multiplier, &self.cross.addition),
-            )
-            .field(
-                "moon",
-                &format!("x{}+{}", &self.moon.multiplier, &self.moon.addition),
-            )
+            .field("diamond", &self.diamond.to_string())
+            .field("cross", &self.cross.to_string())
+            .field("moon", &self.moon.to_string())
             .finish()
     }
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
 pub struct DamageCalculation {
+    #[serde(default)]
     pub multiplier: u8,
+    #[serde(default)]
     pub addition: u8,
+    /// Replaces the multiplier and addition when set.
+    #[serde(default)]
+    #[cfg_attr(feature = "schema", schemars(with = "Option<String>"))]
+    pub formula: Option<Formula>,
+}
+
+impl DamageCalculation {
+    pub fn evaluate(&self, context: &DamageContext) -> u8 {
+        match &self.formula {
+            Some(formula) => formula.evaluate(context),
+            None => context
+                .previous_disasters
+                .saturating_mul(self.multiplier)
+                .saturating_add(self.addition),
+        }
+    }
+}
+
+impl fmt::Display for DamageCalculation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.formula {
+            Some(formula) => write!(f, "{}", formula),
+            None => write!(f, "x{}+{}", self.multiplier, self.addition),
+        }
+    }
+}
+
+/// Consequence of a disaster other than damage.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Effect {
+    /// Every room in the shop goes to the discard pile.
+    DiscardShop,
+    /// The player due to act next loses their turn.
+    SkipNextTurn,
+    /// Every castle loses the removable room farthest from its throne.
+    RemoveOuterRoom,
+    /// Powered rooms give no bonus for the rest of the round.
+    DisablePower,
+}
+
+/// Part of each castle a disaster destroys regardless of connection types.
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Target {
+    /// Every room more than this many links away from a throne.
+    BeyondDistance(u8),
+    /// One removable room, picked at random.
+    RandomOuterRoom,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DamageCalculation, DamageContext, Disaster, Effect};
+
+    #[test]
+    fn test_presentation_round_trip() {
+        let disaster: Disaster = ron::from_str(
+            r#"Disaster(
+                name: "Flood",
+                diamond: DamageCalculation(multiplier: 0, addition: 1),
+                cross: DamageCalculation(multiplier: 0, addition: 1),
+                moon: DamageCalculation(multiplier: 1, addition: 0),
+                art: Some("flood.png"),
+                flavor_text: Some("The moat was a mistake."),
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(disaster.art.as_deref(), Some("flood.png"));
+        assert_eq!(disaster.color, None);
+        assert!(disaster.effects.is_empty());
+        let text = ron::to_string(&disaster).unwrap();
+        assert_eq!(ron::from_str::<Disaster>(&text).unwrap(), disaster);
+    }
+
+    #[test]
+    fn test_effects_round_trip() {
+        let disaster: Disaster = ron::from_str(
+            r#"Disaster(
+                name: "Earthquake",
+                diamond: DamageCalculation(multiplier: 1, addition: 0),
+                cross: DamageCalculation(multiplier: 1, addition: 0),
+                moon: DamageCalculation(multiplier: 1, addition: 0),
+                effects: [RemoveOuterRoom, SkipNextTurn],
+            )"#,
+        )
+        .unwrap();
+        assert_eq!(
+            disaster.effects,
+            vec![Effect::RemoveOuterRoom, Effect::SkipNextTurn]
+        );
+        let text = ron::to_string(&disaster).unwrap();
+        assert_eq!(ron::from_str::<Disaster>(&text).unwrap(), disaster);
+    }
+
+    #[test]
+    fn test_formula_damage() {
+        let disaster: Disaster = ron::from_str(
+            r#"Disaster(
+                name: "Sinkhole",
+                diamond: DamageCalculation(formula: Some("2*prev + max(0, rooms-5)")),
+                cross: DamageCalculation(multiplier: 1, addition: 1),
+                moon: DamageCalculation(),
+            )"#,
+        )
+        .unwrap();
+        let context = DamageContext {
+            previous_disasters: 1,
+            rooms: 7,
+            ..DamageContext::default()
+        };
+        assert_eq!(disaster.damage(&context), [4, 2, 0]);
+        let text = ron::to_string(&disaster).unwrap();
+        assert_eq!(ron::from_str::<Disaster>(&text).unwrap(), disaster);
+        assert!(ron::from_str::<Disaster>(&text.replace("rooms", "towers")).is_err());
+
+        // Large card values clamp instead of overflowing
+        let big = DamageCalculation {
+            multiplier: 200,
+            addition: 200,
+            formula: None,
+        };
+        assert_eq!(big.evaluate(&DamageContext::after(3)), u8::MAX);
+        let finale = Disaster {
+            catastrophe: true,
+            ..disaster
+        };
+        assert_eq!(finale.cross_damage(200), u8::MAX);
+    }
 }
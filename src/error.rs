@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+/// Stable identifier of every failure the crate can report.
+///
+/// The numeric values and the snake_case names are part of the public contract
+/// and must never be reused or renumbered; add new codes at the end of their range.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    // 1xxx: game rules
+    InvalidPlayer = 1000,
+    NotTurnPlayer = 1001,
+    InvalidShopIndex = 1002,
+    StaleAction = 1003,
+    RoomNotInShop = 1004,
+    DeltaMismatch = 1005,
+    InvalidSetting = 1006,
+    NoDefaultMove = 1007,
+    // 2xxx: castle rules
+    CastleError = 2000,
+    // 3xxx: card data
+    UnreadableCardData = 3000,
+    MalformedCardData = 3001,
+    InvalidCardData = 3002,
+    UnsupportedCardFormat = 3003,
+    // 4xxx: persistence
+    UnsupportedVersion = 4000,
+    MalformedState = 4001,
+    MalformedInput = 4002,
+    // 5xxx: joining games
+    UnknownInvite = 5000,
+    ExpiredInvite = 5001,
+    // 9xxx: engine faults
+    Internal = 9000,
+}
+
+impl ErrorCode {
+    pub fn as_u16(self) -> u16 {
+        self as u16
+    }
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::InvalidPlayer => "invalid_player",
+            ErrorCode::NotTurnPlayer => "not_turn_player",
+            ErrorCode::InvalidShopIndex => "invalid_shop_index",
+            ErrorCode::StaleAction => "stale_action",
+            ErrorCode::RoomNotInShop => "room_not_in_shop",
+            ErrorCode::DeltaMismatch => "delta_mismatch",
+            ErrorCode::InvalidSetting => "invalid_setting",
+            ErrorCode::NoDefaultMove => "no_default_move",
+            ErrorCode::CastleError => "castle_error",
+            ErrorCode::UnreadableCardData => "unreadable_card_data",
+            ErrorCode::MalformedCardData => "malformed_card_data",
+            ErrorCode::InvalidCardData => "invalid_card_data",
+            ErrorCode::UnsupportedCardFormat => "unsupported_card_format",
+            ErrorCode::UnsupportedVersion => "unsupported_version",
+            ErrorCode::MalformedState => "malformed_state",
+            ErrorCode::MalformedInput => "malformed_input",
+            ErrorCode::UnknownInvite => "unknown_invite",
+            ErrorCode::ExpiredInvite => "expired_invite",
+            ErrorCode::Internal => "internal",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.as_str(), self.as_u16())
+    }
+}
+
+/// Wire representation of an error, shaped `{ code, message, details }` for API consumers.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ErrorBody {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, String>,
+}
+
+impl ErrorBody {
+    pub fn new(code: ErrorCode, message: String) -> ErrorBody {
+        ErrorBody {
+            code,
+            message,
+            details: BTreeMap::new(),
+        }
+    }
+    pub fn with_detail(mut self, key: &str, value: impl ToString) -> ErrorBody {
+        self.details.insert(key.to_string(), value.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ErrorBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.code, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorBody, ErrorCode};
+    use crate::game::GameError;
+
+    #[test]
+    fn test_game_error_serializes_as_body() {
+        let serialized = ron::to_string(&GameError::NotTurnPlayer).unwrap();
+        let body: ErrorBody = ron::from_str(&serialized).unwrap();
+        assert_eq!(body.code, ErrorCode::NotTurnPlayer);
+        assert_eq!(body.message, GameError::NotTurnPlayer.to_string());
+        assert!(body.details.is_empty());
+    }
+}
@@ -0,0 +1,244 @@
+//! C ABI for embedding the rules engine in native game engines.
+//!
+//! Games live behind opaque `DisastleGame` handles. Values cross the boundary as
+//! NUL-terminated UTF-8 JSON strings; every string returned by this module is owned by the
+//! caller and must be released with `disastle_string_free`. Panics never unwind into the
+//! caller: a function that panics returns null, or an `internal` error body when null would
+//! mean success. The matching header is `include/disastle.h`, regenerated with
+//! `cbindgen --config cbindgen.toml`.
+
+use serde::Serialize;
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_char,
+    panic::{self, AssertUnwindSafe},
+    ptr,
+};
+
+use crate::{
+    error::{ErrorBody, ErrorCode},
+    game::{migrate, GameSetting, GameState, PlayerSecret},
+};
+use disastle_castle_rust::Action;
+
+pub struct DisastleGame {
+    game: GameState,
+}
+
+unsafe fn read_str<'a>(s: *const c_char) -> Result<&'a str, ErrorBody> {
+    if s.is_null() {
+        return Err(ErrorBody::new(
+            ErrorCode::MalformedInput,
+            "Unexpected null string".to_string(),
+        ));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|e| ErrorBody::new(ErrorCode::MalformedInput, e.to_string()))
+}
+
+unsafe fn read_json<T: serde::de::DeserializeOwned>(s: *const c_char) -> Result<T, ErrorBody> {
+    serde_json::from_str(read_str(s)?)
+        .map_err(|e| ErrorBody::new(ErrorCode::MalformedInput, e.to_string()))
+}
+
+fn into_c_string(s: String) -> *mut c_char {
+    // JSON never contains interior NULs, so this only fails on programmer error
+    CString::new(s).map_or(ptr::null_mut(), CString::into_raw)
+}
+
+fn to_c_json<T: Serialize>(value: &T) -> *mut c_char {
+    serde_json::to_string(value).map_or(ptr::null_mut(), into_c_string)
+}
+
+/// Run an exported body, answering `on_panic` instead of unwinding across the boundary.
+fn guard<T>(on_panic: impl FnOnce() -> T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|_| on_panic())
+}
+
+/// Create a game from a JSON array of player secrets and a JSON `GameSetting`.
+/// Returns null if either argument is malformed or the setting cannot deal a game for the
+/// players.
+///
+/// # Safety
+/// Both arguments must be null or valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_game_new(
+    players: *const c_char,
+    setting: *const c_char,
+) -> *mut DisastleGame {
+    guard(ptr::null_mut, || {
+        let players: Vec<PlayerSecret> = match read_json(players) {
+            Ok(players) => players,
+            Err(_) => return ptr::null_mut(),
+        };
+        let setting: GameSetting = match read_json(setting) {
+            Ok(setting) => setting,
+            Err(_) => return ptr::null_mut(),
+        };
+        match GameState::new(players, setting) {
+            Ok(game) => Box::into_raw(Box::new(DisastleGame { game })),
+            Err(_) => ptr::null_mut(),
+        }
+    })
+}
+
+/// Load a game saved with `disastle_game_to_json`, from any supported schema version.
+/// Returns null if the state can't be loaded.
+///
+/// # Safety
+/// `state` must be null or a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_game_from_json(state: *const c_char) -> *mut DisastleGame {
+    guard(ptr::null_mut, || match read_json(state).map(migrate) {
+        Ok(Ok(game)) => Box::into_raw(Box::new(DisastleGame { game })),
+        _ => ptr::null_mut(),
+    })
+}
+
+/// Serialize the full game state, including hidden information, for saving.
+///
+/// # Safety
+/// `game` must be a live handle returned by this module.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_game_to_json(game: *const DisastleGame) -> *mut c_char {
+    guard(ptr::null_mut, || match game.as_ref() {
+        Some(handle) => to_c_json(&handle.game.to_versioned()),
+        None => ptr::null_mut(),
+    })
+}
+
+/// The JSON `PlayerView` for `secret`; unknown secrets get the spectator view.
+///
+/// # Safety
+/// `game` must be a live handle and `secret` a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_game_player_view(
+    game: *const DisastleGame,
+    secret: *const c_char,
+) -> *mut c_char {
+    guard(ptr::null_mut, || match (game.as_ref(), read_str(secret)) {
+        (Some(handle), Ok(secret)) => {
+            to_c_json(&handle.game.player_view(&PlayerSecret::from(secret)))
+        }
+        _ => ptr::null_mut(),
+    })
+}
+
+/// Apply a JSON `Action` in place. Returns null on success, or a JSON `ErrorBody`
+/// describing why the action was rejected, in which case the game is unchanged.
+///
+/// # Safety
+/// `game` must be a live handle; `secret` and `action` valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_game_action(
+    game: *mut DisastleGame,
+    secret: *const c_char,
+    expected_seq: u64,
+    action: *const c_char,
+) -> *mut c_char {
+    guard(
+        || {
+            to_c_json(&ErrorBody::new(
+                ErrorCode::Internal,
+                "The rules engine panicked".to_string(),
+            ))
+        },
+        || {
+            let handle = match game.as_mut() {
+                Some(handle) => handle,
+                None => {
+                    return to_c_json(&ErrorBody::new(
+                        ErrorCode::MalformedInput,
+                        "Unexpected null game".to_string(),
+                    ))
+                }
+            };
+            let result = read_str(secret).and_then(|secret| {
+                let action: Action = read_json(action)?;
+                handle
+                    .game
+                    .action(&PlayerSecret::from(secret), expected_seq, action)
+                    .map_err(|e| e.to_body())
+            });
+            match result {
+                Ok(game) => {
+                    handle.game = game;
+                    ptr::null_mut()
+                }
+                Err(body) => to_c_json(&body),
+            }
+        },
+    )
+}
+
+/// # Safety
+/// `game` must be null or a handle returned by this module that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_game_free(game: *mut DisastleGame) {
+    guard(
+        || (),
+        || {
+            if !game.is_null() {
+                drop(Box::from_raw(game));
+            }
+        },
+    )
+}
+
+/// # Safety
+/// `s` must be null or a string returned by this module that has not been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn disastle_string_free(s: *mut c_char) {
+    guard(
+        || (),
+        || {
+            if !s.is_null() {
+                drop(CString::from_raw(s));
+            }
+        },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    unsafe fn take(s: *mut c_char) -> String {
+        let owned = CStr::from_ptr(s).to_str().unwrap().to_string();
+        disastle_string_free(s);
+        owned
+    }
+
+    #[test]
+    fn rejects_bad_action_and_round_trips() {
+        unsafe {
+            let game = Box::into_raw(Box::new(DisastleGame {
+                game: crate::game::test_game(&["a", "b"]),
+            }));
+            let secret = CString::new("nobody").unwrap();
+            let action = CString::new("{}").unwrap();
+            let error: ErrorBody = serde_json::from_str(&take(disastle_game_action(
+                game,
+                secret.as_ptr(),
+                0,
+                action.as_ptr(),
+            )))
+            .unwrap();
+            assert_eq!(error.code, ErrorCode::MalformedInput);
+
+            let saved = CString::new(take(disastle_game_to_json(game))).unwrap();
+            let loaded = disastle_game_from_json(saved.as_ptr());
+            assert!(!loaded.is_null());
+            assert_eq!((*loaded).game, (*game).game);
+            disastle_game_free(loaded);
+            disastle_game_free(game);
+        }
+    }
+
+    #[test]
+    fn panics_do_not_unwind() {
+        assert!(guard(ptr::null_mut::<c_char>, || panic!("engine bug")).is_null());
+        assert_eq!(guard(|| 0, || 1), 1);
+    }
+}
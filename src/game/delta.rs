@@ -0,0 +1,395 @@
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    result,
+};
+
+use super::{
+    card::Card, error::GameError, Forecast, GameEvent, GameSetting, GameState, PlayerSecret,
+    PlayerView,
+};
+use crate::disaster::Disaster;
+use disastle_castle_rust::{Action, Castle, Room};
+
+type Result<T> = result::Result<T, GameError>;
+
+/// Change to a list between two states. Piles that only grow (discard, resolved disasters)
+/// are sent as the appended tail, everything else is replaced wholesale.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ListDelta<T> {
+    Unchanged,
+    Append(Vec<T>),
+    Replace(Vec<T>),
+}
+
+impl<T: Clone + PartialEq> ListDelta<T> {
+    pub fn diff(older: &[T], newer: &[T]) -> ListDelta<T> {
+        if older == newer {
+            ListDelta::Unchanged
+        } else if newer.starts_with(older) {
+            ListDelta::Append(newer[older.len()..].to_vec())
+        } else {
+            ListDelta::Replace(newer.to_vec())
+        }
+    }
+    pub fn apply(&self, list: &mut Vec<T>) {
+        match self {
+            ListDelta::Unchanged => {}
+            ListDelta::Append(items) => list.extend(items.iter().cloned()),
+            ListDelta::Replace(items) => *list = items.clone(),
+        }
+    }
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, ListDelta::Unchanged)
+    }
+}
+
+/// Patch turning the game state at `from_seq` into the state at `to_seq`.
+///
+/// Only castles that changed are included. Field visibility mirrors `GameState`. The delta
+/// carries the deck and the player secrets, so it is only for trusted peers such as other
+/// servers or storage; players get a `ViewDelta`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDelta {
+    pub from_seq: u64,
+    pub to_seq: u64,
+    pub shop: ListDelta<Room>,
+    pub discard: ListDelta<Room>,
+    pub previous_disasters: ListDelta<Disaster>,
+    pub queued_disasters: ListDelta<Disaster>,
+    pub forecasts: ListDelta<Forecast>,
+    pub round: Option<u8>,
+    pub setting: Option<GameSetting>,
+    pub events: ListDelta<GameEvent>,
+    castles: BTreeMap<PlayerSecret, Castle>,
+    deck: ListDelta<Card>,
+    turn_order: ListDelta<PlayerSecret>,
+    turn_index: Option<usize>,
+    power_disabled_round: Option<Option<u8>>,
+    #[serde(default)]
+    resigned: Option<BTreeSet<PlayerSecret>>,
+    #[serde(default)]
+    timeouts: Option<BTreeMap<PlayerSecret, u8>>,
+    #[serde(default)]
+    abort_votes: Option<BTreeSet<PlayerSecret>>,
+    #[serde(default)]
+    aborted: Option<bool>,
+}
+
+impl StateDelta {
+    pub fn is_empty(&self) -> bool {
+        self.from_seq == self.to_seq
+            && self.shop.is_unchanged()
+            && self.discard.is_unchanged()
+            && self.previous_disasters.is_unchanged()
+            && self.queued_disasters.is_unchanged()
+            && self.forecasts.is_unchanged()
+            && self.round.is_none()
+            && self.setting.is_none()
+            && self.events.is_unchanged()
+            && self.castles.is_empty()
+            && self.deck.is_unchanged()
+            && self.turn_order.is_unchanged()
+            && self.turn_index.is_none()
+            && self.power_disabled_round.is_none()
+            && self.resigned.is_none()
+            && self.timeouts.is_none()
+            && self.abort_votes.is_none()
+            && self.aborted.is_none()
+    }
+    pub fn changed_castles(&self) -> impl Iterator<Item = &PlayerSecret> {
+        self.castles.keys()
+    }
+}
+
+/// Patch turning one player's `PlayerView` at `from_seq` into their view at `to_seq`. Like
+/// the view, it identifies players by seat and only tells the size of the deck.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ViewDelta {
+    pub from_seq: u64,
+    pub to_seq: u64,
+    /// Castles that changed, by seat.
+    pub castles: BTreeMap<usize, Castle>,
+    pub turn_order: Option<Vec<usize>>,
+    pub to_act: Option<Vec<usize>>,
+    pub shop: ListDelta<Room>,
+    pub discard: ListDelta<Room>,
+    pub previous_disasters: ListDelta<Disaster>,
+    pub queued_disasters: ListDelta<Disaster>,
+    pub forecasts: ListDelta<Forecast>,
+    pub deck_size: Option<usize>,
+    pub round: Option<u8>,
+    pub events: ListDelta<GameEvent>,
+    pub possible_actions: Option<Vec<Action>>,
+    pub is_over: Option<bool>,
+    pub power_disabled: Option<bool>,
+    pub resigned: Option<Vec<usize>>,
+    pub abort_votes: Option<Vec<usize>>,
+}
+
+impl ViewDelta {
+    pub fn is_empty(&self) -> bool {
+        self.from_seq == self.to_seq
+            && self.castles.is_empty()
+            && self.turn_order.is_none()
+            && self.to_act.is_none()
+            && self.shop.is_unchanged()
+            && self.discard.is_unchanged()
+            && self.previous_disasters.is_unchanged()
+            && self.queued_disasters.is_unchanged()
+            && self.forecasts.is_unchanged()
+            && self.deck_size.is_none()
+            && self.round.is_none()
+            && self.events.is_unchanged()
+            && self.possible_actions.is_none()
+            && self.is_over.is_none()
+            && self.power_disabled.is_none()
+            && self.resigned.is_none()
+            && self.abort_votes.is_none()
+    }
+}
+
+impl PlayerView {
+    /// Compute the patch that turns `older`, a view for the same seat, into `self`.
+    pub fn diff(&self, older: &PlayerView) -> ViewDelta {
+        let castles = self
+            .castles
+            .iter()
+            .enumerate()
+            .filter(|(seat, castle)| older.castles.get(*seat) != Some(castle))
+            .map(|(seat, castle)| (seat, castle.clone()))
+            .collect();
+        ViewDelta {
+            from_seq: older.action_seq,
+            to_seq: self.action_seq,
+            castles,
+            turn_order: changed(&older.turn_order, &self.turn_order),
+            to_act: changed(&older.to_act, &self.to_act),
+            shop: ListDelta::diff(&older.shop, &self.shop),
+            discard: ListDelta::diff(&older.discard, &self.discard),
+            previous_disasters: ListDelta::diff(
+                &older.previous_disasters,
+                &self.previous_disasters,
+            ),
+            queued_disasters: ListDelta::diff(&older.queued_disasters, &self.queued_disasters),
+            forecasts: ListDelta::diff(&older.forecasts, &self.forecasts),
+            deck_size: changed(&older.deck_size, &self.deck_size),
+            round: changed(&older.round, &self.round),
+            events: ListDelta::diff(&older.events, &self.events),
+            possible_actions: changed(&older.possible_actions, &self.possible_actions),
+            is_over: changed(&older.is_over, &self.is_over),
+            power_disabled: changed(&older.power_disabled, &self.power_disabled),
+            resigned: changed(&older.resigned, &self.resigned),
+            abort_votes: changed(&older.abort_votes, &self.abort_votes),
+        }
+    }
+    pub fn apply_delta(&self, delta: &ViewDelta) -> Result<PlayerView> {
+        if delta.from_seq != self.action_seq {
+            return Err(GameError::DeltaMismatch {
+                from_seq: delta.from_seq,
+                current_seq: self.action_seq,
+            });
+        }
+        let mut view = self.clone();
+        view.action_seq = delta.to_seq;
+        for (&seat, castle) in delta.castles.iter() {
+            if seat < view.castles.len() {
+                view.castles[seat] = castle.clone();
+            } else {
+                view.castles.push(castle.clone());
+            }
+        }
+        if let Some(turn_order) = &delta.turn_order {
+            view.turn_order = turn_order.clone();
+        }
+        if let Some(to_act) = &delta.to_act {
+            view.to_act = to_act.clone();
+        }
+        delta.shop.apply(&mut view.shop);
+        delta.discard.apply(&mut view.discard);
+        delta.previous_disasters.apply(&mut view.previous_disasters);
+        delta.queued_disasters.apply(&mut view.queued_disasters);
+        delta.forecasts.apply(&mut view.forecasts);
+        if let Some(deck_size) = delta.deck_size {
+            view.deck_size = deck_size;
+        }
+        if let Some(round) = delta.round {
+            view.round = round;
+        }
+        delta.events.apply(&mut view.events);
+        if let Some(possible_actions) = &delta.possible_actions {
+            view.possible_actions = possible_actions.clone();
+        }
+        if let Some(is_over) = delta.is_over {
+            view.is_over = is_over;
+        }
+        if let Some(power_disabled) = delta.power_disabled {
+            view.power_disabled = power_disabled;
+        }
+        if let Some(resigned) = &delta.resigned {
+            view.resigned = resigned.clone();
+        }
+        if let Some(abort_votes) = &delta.abort_votes {
+            view.abort_votes = abort_votes.clone();
+        }
+        Ok(view)
+    }
+}
+
+fn changed<T: Clone + PartialEq>(older: &T, newer: &T) -> Option<T> {
+    if older == newer {
+        None
+    } else {
+        Some(newer.clone())
+    }
+}
+
+impl GameState {
+    /// The delta of the player's view applying `action` would produce, leaving the game
+    /// untouched, so a client can show its move at once and check it against the
+    /// authoritative delta later. The prediction is exact unless the move starts a new round,
+    /// which draws at random.
+    #[cfg(feature = "std")]
+    pub fn predict(
+        &self,
+        player_secret: &PlayerSecret,
+        expected_seq: u64,
+        action: Action,
+    ) -> Result<ViewDelta> {
+        self.predict_with_rng(player_secret, expected_seq, action, &mut thread_rng())
+    }
+    pub fn predict_with_rng<R: Rng + ?Sized>(
+        &self,
+        player_secret: &PlayerSecret,
+        expected_seq: u64,
+        action: Action,
+        rng: &mut R,
+    ) -> Result<ViewDelta> {
+        Ok(self
+            .action_with_rng(player_secret, expected_seq, action, rng)?
+            .view_delta(self, player_secret))
+    }
+    /// The patch that turns the view of `secret` of `older` into their view of `self`.
+    pub fn view_delta(&self, older: &GameState, secret: &PlayerSecret) -> ViewDelta {
+        self.player_view(secret).diff(&older.player_view(secret))
+    }
+    /// Compute the patch that turns `older` into `self`.
+    pub fn diff(&self, older: &GameState) -> StateDelta {
+        let castles = self
+            .castles
+            .iter()
+            .filter(|(secret, castle)| older.castles.get(secret) != Some(castle))
+            .map(|(secret, castle)| (secret.clone(), castle.clone()))
+            .collect();
+        StateDelta {
+            from_seq: older.action_seq,
+            to_seq: self.action_seq,
+            shop: ListDelta::diff(&older.shop, &self.shop),
+            discard: ListDelta::diff(&older.discard, &self.discard),
+            previous_disasters: ListDelta::diff(
+                &older.previous_disasters,
+                &self.previous_disasters,
+            ),
+            queued_disasters: ListDelta::diff(&older.queued_disasters, &self.queued_disasters),
+            forecasts: ListDelta::diff(&older.forecasts, &self.forecasts),
+            round: changed(&older.round, &self.round),
+            setting: changed(&older.setting, &self.setting),
+            events: ListDelta::diff(&older.events, &self.events),
+            castles,
+            deck: ListDelta::diff(&older.deck, &self.deck),
+            turn_order: ListDelta::diff(&older.turn_order, &self.turn_order),
+            turn_index: changed(&older.turn_index, &self.turn_index),
+            power_disabled_round: changed(&older.power_disabled_round, &self.power_disabled_round),
+            resigned: changed(&older.resigned, &self.resigned),
+            timeouts: changed(&older.timeouts, &self.timeouts),
+            abort_votes: changed(&older.abort_votes, &self.abort_votes),
+            aborted: changed(&older.aborted, &self.aborted),
+        }
+    }
+    pub fn apply_delta(&self, delta: &StateDelta) -> Result<GameState> {
+        if delta.from_seq != self.action_seq {
+            return Err(GameError::DeltaMismatch {
+                from_seq: delta.from_seq,
+                current_seq: self.action_seq,
+            });
+        }
+        let mut game = self.clone();
+        game.action_seq = delta.to_seq;
+        delta.shop.apply(&mut game.shop);
+        delta.discard.apply(&mut game.discard);
+        delta.previous_disasters.apply(&mut game.previous_disasters);
+        delta.queued_disasters.apply(&mut game.queued_disasters);
+        delta.forecasts.apply(&mut game.forecasts);
+        if let Some(round) = delta.round {
+            game.round = round;
+        }
+        if let Some(setting) = &delta.setting {
+            game.setting = setting.clone();
+        }
+        delta.events.apply(&mut game.events);
+        for (secret, castle) in delta.castles.iter() {
+            game.castles.insert(secret.clone(), castle.clone());
+        }
+        delta.deck.apply(&mut game.deck);
+        delta.turn_order.apply(&mut game.turn_order);
+        if let Some(turn_index) = delta.turn_index {
+            game.turn_index = turn_index;
+        }
+        if let Some(power_disabled_round) = delta.power_disabled_round {
+            game.power_disabled_round = power_disabled_round;
+        }
+        if let Some(resigned) = &delta.resigned {
+            game.resigned = resigned.clone();
+        }
+        if let Some(timeouts) = &delta.timeouts {
+            game.timeouts = timeouts.clone();
+        }
+        if let Some(abort_votes) = &delta.abort_votes {
+            game.abort_votes = abort_votes.clone();
+        }
+        if let Some(aborted) = delta.aborted {
+            game.aborted = aborted;
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StateDelta, ViewDelta};
+    use crate::game::test_game;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_diff_apply_round_trip() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a", "b", "c"]);
+        let secret = game.turn_order[game.turn_index].clone();
+        let action = game.possible_actions(&secret).remove(0);
+        let next = game
+            .action_with_rng(&secret, 0, action.clone(), &mut rng)
+            .unwrap();
+        let delta = next.diff(&game);
+        assert!(!delta.is_empty());
+        assert_eq!(delta.changed_castles().collect::<Vec<_>>(), vec![&secret]);
+        let delta: StateDelta = ron::from_str(&ron::to_string(&delta).unwrap()).unwrap();
+        assert_eq!(game.apply_delta(&delta).unwrap(), next);
+        assert!(next.apply_delta(&delta).is_err());
+        assert!(next.diff(&next).is_empty());
+
+        let view_delta = next.view_delta(&game, &secret);
+        let predicted = game.predict_with_rng(&secret, 0, action, &mut rng);
+        assert_eq!(predicted.unwrap(), view_delta);
+        let seat = game.get_seat(&secret).unwrap();
+        assert_eq!(view_delta.castles.keys().collect::<Vec<_>>(), vec![&seat]);
+        let serialized = ron::to_string(&view_delta).unwrap();
+        assert!(!serialized.contains(&ron::to_string(&secret).unwrap()));
+        let view_delta: ViewDelta = ron::from_str(&serialized).unwrap();
+        let view = game.player_view(&secret).apply_delta(&view_delta).unwrap();
+        assert_eq!(view, next.player_view(&secret));
+        assert!(next.view_delta(&next, &secret).is_empty());
+    }
+}
@@ -1,33 +1,132 @@
-use disastle_castle_rust::CastleError;
-use std::{error::Error, fmt};
+use crate::{
+    error::{ErrorBody, ErrorCode},
+    pos::Pos,
+};
+use disastle_castle_rust::{Action, CastleError};
+use serde::{Serialize, Serializer};
+use thiserror::Error;
 
-#[derive(Debug)]
+#[derive(Debug, Error)]
 pub enum GameError {
+    #[error("There is no player with matching secret in game.")]
     InvalidPlayer,
+    #[error("It is not the turn of the player yet.")]
     NotTurnPlayer,
-    InvalidShopIndex,
-    CastleError(CastleError),
+    #[error("Shop index {index} is out of bounds for a shop of {shop_len} rooms")]
+    InvalidShopIndex { index: usize, shop_len: usize },
+    #[error("The requested room is not in the shop")]
+    RoomNotInShop,
+    #[error("Action was based on sequence {expected_seq} but the game is at {current_seq}")]
+    StaleAction { expected_seq: u64, current_seq: u64 },
+    #[error("Delta starts at sequence {from_seq} but the game is at {current_seq}")]
+    DeltaMismatch { from_seq: u64, current_seq: u64 },
+    #[error("Castle error: {error} while applying {action:?}")]
+    CastleError { action: Action, error: CastleError },
+    #[error("Invalid game setting: {}", describe(.0))]
+    InvalidSetting(Vec<SettingError>),
+    #[error("The player owes damage but has no room they can discard")]
+    NoDefaultMove,
 }
 
-impl From<CastleError> for GameError {
-    fn from(error: CastleError) -> Self {
-        Self::CastleError(error)
+fn describe(errors: &[SettingError]) -> String {
+    let messages: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+    messages.join("; ")
+}
+
+impl From<Vec<SettingError>> for GameError {
+    fn from(errors: Vec<SettingError>) -> GameError {
+        GameError::InvalidSetting(errors)
     }
 }
 
-impl fmt::Display for GameError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl GameError {
+    pub fn code(&self) -> ErrorCode {
         match self {
-            GameError::InvalidPlayer => {
-                write!(f, "There is no player with matching secret in game.")
-            }
-            GameError::NotTurnPlayer => {
-                write!(f, "It is not the turn of the player yet.")
+            GameError::InvalidPlayer => ErrorCode::InvalidPlayer,
+            GameError::NotTurnPlayer => ErrorCode::NotTurnPlayer,
+            GameError::InvalidShopIndex { .. } => ErrorCode::InvalidShopIndex,
+            GameError::RoomNotInShop => ErrorCode::RoomNotInShop,
+            GameError::StaleAction { .. } => ErrorCode::StaleAction,
+            GameError::DeltaMismatch { .. } => ErrorCode::DeltaMismatch,
+            GameError::CastleError { .. } => ErrorCode::CastleError,
+            GameError::InvalidSetting(_) => ErrorCode::InvalidSetting,
+            GameError::NoDefaultMove => ErrorCode::NoDefaultMove,
+        }
+    }
+    pub fn to_body(&self) -> ErrorBody {
+        let body = ErrorBody::new(self.code(), self.to_string());
+        match self {
+            GameError::InvalidShopIndex { index, shop_len } => body
+                .with_detail("index", index)
+                .with_detail("shop_len", shop_len),
+            GameError::StaleAction {
+                expected_seq,
+                current_seq,
+            } => body
+                .with_detail("expected_seq", expected_seq)
+                .with_detail("current_seq", current_seq),
+            GameError::DeltaMismatch {
+                from_seq,
+                current_seq,
+            } => body
+                .with_detail("from_seq", from_seq)
+                .with_detail("current_seq", current_seq),
+            GameError::CastleError { action, error } => {
+                let body = body
+                    .with_detail("castle_error", format!("{:?}", error))
+                    .with_detail("action", format!("{:?}", action));
+                match action {
+                    Action::Place(index, pos) => body
+                        .with_detail("index", index)
+                        .with_detail("pos", Pos::from(*pos)),
+                    Action::Move(from, to) => body
+                        .with_detail("from", Pos::from(*from))
+                        .with_detail("to", Pos::from(*to)),
+                    Action::Swap(pos1, pos2) => body
+                        .with_detail("pos1", Pos::from(*pos1))
+                        .with_detail("pos2", Pos::from(*pos2)),
+                    Action::Discard(pos) => body.with_detail("pos", Pos::from(*pos)),
+                }
             }
-            GameError::InvalidShopIndex => write!(f, "Shop index is out of bounds"),
-            GameError::CastleError(e) => write!(f, "Castle error: {}", e),
+            _ => body,
+        }
+    }
+    pub(crate) fn castle(action: &Action, error: CastleError) -> GameError {
+        GameError::CastleError {
+            action: action.clone(),
+            error,
         }
     }
 }
 
-impl Error for GameError {}
+/// Setting a game cannot be dealt from.
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum SettingError {
+    #[error("The shop must hold at least one room")]
+    EmptyShop,
+    #[error("A shop of {num_shop} rooms cannot be dealt from {num_safe} safe rooms")]
+    ShopLargerThanSafe { num_shop: u8, num_safe: u8 },
+    #[error("{needed} safe rooms are needed but the deck has {available} rooms")]
+    NotEnoughRooms { needed: usize, available: usize },
+    #[error("{needed} disasters are needed but the setting has {available}")]
+    NotEnoughDisasters { needed: usize, available: usize },
+    #[error("{needed} thrones are needed but the setting has {available}")]
+    NotEnoughThrones { needed: usize, available: usize },
+    #[error("An abort must need between 1 and {players} votes, not {needed}")]
+    InvalidAbortVotes { needed: u8, players: usize },
+}
+
+impl SettingError {
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::InvalidSetting
+    }
+    pub fn to_body(&self) -> ErrorBody {
+        ErrorBody::new(self.code(), self.to_string())
+    }
+}
+
+impl Serialize for GameError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_body().serialize(serializer)
+    }
+}
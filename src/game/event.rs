@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+/// Something that happened during the game worth showing to players, appended to
+/// `GameState::events` in the order it happened.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum GameEvent {
+    /// The catastrophe named `disaster` will strike when the shop is next refilled.
+    CatastropheAnnounced { round: u8, disaster: String },
+    /// Damage absorbed by the shields of the castle at `seat`.
+    DamageMitigated {
+        round: u8,
+        seat: usize,
+        diamond: u8,
+        cross: u8,
+        moon: u8,
+    },
+    /// The player at `seat` withdrew from the game.
+    PlayerResigned { round: u8, seat: usize },
+    /// The turn timer of the player at `seat` ran out and the default move was played.
+    TimedOut { round: u8, seat: usize },
+    /// The game was ended with no winner, by a vote of the players or by the host.
+    GameAborted { round: u8 },
+}
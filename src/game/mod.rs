@@ -1,8 +1,21 @@
 mod card;
+mod delta;
 mod error;
+mod event;
+#[cfg(feature = "std")]
+mod replay;
+mod rules;
 mod schrodinger;
+mod secret;
+#[cfg(feature = "fs")]
+mod snapshot;
+mod summary;
+mod version;
+mod view;
 
-use rand::{prelude::IteratorRandom, seq::SliceRandom, thread_rng};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{prelude::IteratorRandom, seq::SliceRandom, Rng};
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
@@ -12,12 +25,27 @@ use std::{
     result,
 };
 
-pub use error::GameError;
+pub use error::{GameError, SettingError};
+pub use event::GameEvent;
 
 pub use crate::disaster::Disaster;
+use crate::{
+    castle::CastleExt,
+    pos::Pos,
+    room::{rotation_from, RoomCard},
+};
 use card::Card;
+pub use delta::{ListDelta, StateDelta, ViewDelta};
 use disastle_castle_rust::{Action, Castle, Room};
+#[cfg(feature = "std")]
+pub use replay::{Recorder, Replay, ReplayStep};
 pub use schrodinger::SchrodingerGameState;
+pub use secret::PlayerSecret;
+#[cfg(feature = "fs")]
+pub use snapshot::SnapshotFormat;
+pub use summary::GameSummary;
+pub use version::{migrate, migrate_schrodinger, MigrationError, Versioned, SCHEMA_VERSION};
+pub use view::{DelayedViews, PlayerView};
 
 type Result<T> = result::Result<T, GameError>;
 
@@ -27,12 +55,32 @@ pub struct GameState {
     pub discard: Vec<Room>,
     pub previous_disasters: Vec<Disaster>,
     pub queued_disasters: Vec<Disaster>,
+    /// Disasters drawn but not due yet, in the order they will strike.
+    #[serde(default)]
+    pub forecasts: Vec<Forecast>,
     pub round: u8,
     pub setting: GameSetting,
-    castles: BTreeMap<String, Castle>,
+    #[serde(default)]
+    pub events: Vec<GameEvent>,
+    castles: BTreeMap<PlayerSecret, Castle>,
     deck: Vec<Card>,
-    turn_order: Vec<String>,
+    turn_order: Vec<PlayerSecret>,
     turn_index: usize,
+    #[serde(default)]
+    action_seq: u64,
+    /// Round during which powered rooms give no bonus.
+    #[serde(default)]
+    power_disabled_round: Option<u8>,
+    #[serde(default)]
+    resigned: BTreeSet<PlayerSecret>,
+    /// Turns in a row each player let time out, absent once they act.
+    #[serde(default)]
+    timeouts: BTreeMap<PlayerSecret, u8>,
+    #[serde(default)]
+    abort_votes: BTreeSet<PlayerSecret>,
+    /// Whether enough players voted to end the game with no winner.
+    #[serde(default)]
+    aborted: bool,
 }
 
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
@@ -43,28 +91,253 @@ pub struct GameSetting {
     pub thrones: BTreeSet<Room>,
     pub rooms: BTreeSet<Room>,
     pub disasters: BTreeSet<Disaster>,
+    /// Rooms dealt into the deck more or less than once. Rooms not listed have one copy.
+    #[serde(default)]
+    pub copies: Vec<RoomCopies>,
+    /// Rooms absorbing disaster damage while powered.
+    #[serde(default)]
+    pub shields: Vec<RoomShield>,
+    #[serde(default)]
+    pub disaster_order: DisasterOrder,
+    /// Rounds between a disaster being drawn and striking. Zero strikes at once.
+    #[serde(default)]
+    pub forecast_rounds: u8,
+    /// Votes needed to abort the game, capped at the players who have not resigned. `None`
+    /// asks for a majority of them.
+    #[serde(default)]
+    pub abort_votes_needed: Option<u8>,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct RoomCopies {
+    pub room: Room,
+    pub copies: u8,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct RoomShield {
+    pub room: Room,
+    pub shield: u8,
+}
+
+/// Disaster revealed ahead of time, striking at the start of `strike_round`.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Forecast {
+    pub disaster: Disaster,
+    pub strike_round: u8,
+}
+
+/// How the disasters of a game are spread through the deck.
+#[derive(
+    Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize,
+)]
+pub enum DisasterOrder {
+    /// Shuffled uniformly with the rooms.
+    #[default]
+    Shuffled,
+    /// One disaster in each of as many equal slices of the deck, from the mildest in the
+    /// first slice dealt to the most severe in the last.
+    Escalating,
+}
+
+impl GameSetting {
+    /// Setting of the base game over the given cards: 5 safe rooms, a shop of 5 and 6
+    /// disasters.
+    pub fn base(thrones: Vec<Room>, rooms: Vec<Room>, disasters: Vec<Disaster>) -> GameSetting {
+        GameSetting {
+            num_safe: 5,
+            num_shop: 5,
+            num_disasters: 6,
+            thrones: thrones.into_iter().collect(),
+            rooms: rooms.into_iter().collect(),
+            disasters: disasters.into_iter().collect(),
+            copies: Vec::new(),
+            shields: Vec::new(),
+            disaster_order: DisasterOrder::Shuffled,
+            forecast_rounds: 0,
+            abort_votes_needed: None,
+        }
+    }
+    pub fn copies_of(&self, room: &Room) -> u8 {
+        self.copies
+            .iter()
+            .find(|c| &c.room == room)
+            .map_or(1, |c| c.copies)
+    }
+    /// Rooms in the deck, counting every copy.
+    pub fn num_rooms(&self) -> usize {
+        self.rooms.iter().map(|r| self.copies_of(r) as usize).sum()
+    }
+    /// Shield of `room` in any rotation.
+    pub fn shield_of(&self, room: &Room) -> u8 {
+        self.shields
+            .iter()
+            .find(|s| rotation_from(&s.room, room).is_some())
+            .map_or(0, |s| s.shield)
+    }
+    /// Total shield of the powered rooms of `castle`.
+    pub fn castle_shield(&self, castle: &Castle) -> u8 {
+        castle
+            .powered_rooms()
+            .into_iter()
+            .map(|pos| self.shield_of(castle.room_at(pos).unwrap()))
+            .fold(0, u8::saturating_add)
+    }
+    /// Add the rooms of `cards` to the setting along with their number of copies and shield.
+    pub fn with_cards(mut self, cards: &[RoomCard]) -> GameSetting {
+        for card in cards {
+            self.rooms.insert(card.room.clone());
+            self.copies.retain(|c| c.room != card.room);
+            if card.copies != 1 {
+                self.copies.push(RoomCopies {
+                    room: card.room.clone(),
+                    copies: card.copies,
+                });
+            }
+            self.shields.retain(|s| s.room != card.room);
+            if card.shield() > 0 {
+                self.shields.push(RoomShield {
+                    room: card.room.clone(),
+                    shield: card.shield(),
+                });
+            }
+        }
+        self
+    }
+    /// Rank of `disaster` for escalating decks: its total damage halfway through the game.
+    pub fn severity(&self, disaster: &Disaster) -> u16 {
+        disaster.total_damage(self.num_disasters / 2)
+    }
+    /// Every reason a game for `players` players could not be dealt from this setting.
+    pub fn validate(&self, players: usize) -> result::Result<(), Vec<SettingError>> {
+        let mut errors = Vec::new();
+        if self.num_shop == 0 {
+            errors.push(SettingError::EmptyShop);
+        }
+        // The first shop is dealt from the safe rooms only
+        if self.num_shop > self.num_safe {
+            errors.push(SettingError::ShopLargerThanSafe {
+                num_shop: self.num_shop,
+                num_safe: self.num_safe,
+            });
+        }
+        let rooms = self.num_rooms();
+        if rooms < self.num_safe as usize {
+            errors.push(SettingError::NotEnoughRooms {
+                needed: self.num_safe as usize,
+                available: rooms,
+            });
+        }
+        // At most one catastrophe is dealt, as the finale
+        let catastrophes = self.disasters.iter().filter(|d| d.catastrophe).count();
+        let disasters = self.disasters.len() - catastrophes + catastrophes.min(1);
+        if disasters < self.num_disasters as usize {
+            errors.push(SettingError::NotEnoughDisasters {
+                needed: self.num_disasters as usize,
+                available: disasters,
+            });
+        }
+        if self.thrones.len() < players {
+            errors.push(SettingError::NotEnoughThrones {
+                needed: players,
+                available: self.thrones.len(),
+            });
+        }
+        match self.abort_votes_needed {
+            Some(needed) if needed == 0 || needed as usize > players => {
+                errors.push(SettingError::InvalidAbortVotes { needed, players })
+            }
+            _ => {}
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+    /// Every throne and room of the setting in a stable order, for identifying rooms by
+    /// index as castle codes do.
+    pub fn catalog(&self) -> Vec<Room> {
+        self.thrones
+            .iter()
+            .chain(self.rooms.iter())
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(feature = "bundled-cards")]
+impl GameSetting {
+    /// The base game: the bundled rooms, thrones and disasters, 5 safe rooms, a shop of 5
+    /// and 6 disasters.
+    pub fn standard() -> GameSetting {
+        use crate::{bundled, load_disasters_from_str, load_rooms_from_str, load_thrones_from_str};
+        GameSetting::base(
+            load_thrones_from_str(bundled::THRONES).expect("bundled thrones are valid"),
+            load_rooms_from_str(bundled::ROOMS).expect("bundled rooms are valid"),
+            load_disasters_from_str(bundled::DISASTERS).expect("bundled disasters are valid"),
+        )
+    }
 }
 
 impl GameState {
-    pub fn new(players: Vec<String>, setting: GameSetting) -> GameState {
-        let mut rng = rand::thread_rng();
-        let mut deck: Vec<Room> = setting.rooms.clone().into_iter().collect();
-        deck.shuffle(&mut rng);
+    /// Deal a game, provided `setting` passes `GameSetting::validate` for the players.
+    #[cfg(feature = "std")]
+    pub fn new(
+        players: Vec<PlayerSecret>,
+        setting: GameSetting,
+    ) -> result::Result<GameState, Vec<SettingError>> {
+        GameState::new_with_rng(players, setting, &mut thread_rng())
+    }
+    pub fn new_with_rng<R: Rng + ?Sized>(
+        players: Vec<PlayerSecret>,
+        setting: GameSetting,
+        rng: &mut R,
+    ) -> result::Result<GameState, Vec<SettingError>> {
+        setting.validate(players.len())?;
+        let mut deck: Vec<Room> = setting
+            .rooms
+            .iter()
+            .flat_map(|room| (0..setting.copies_of(room)).map(move |_| room.clone()))
+            .collect();
+        deck.shuffle(rng);
         let mut safe = deck
             .drain(deck.len() - setting.num_safe as usize..)
             .map(|r| Card::Room(r))
             .collect();
-        let mut deck: Vec<Card> = deck.into_iter().map(|r| Card::Room(r)).collect();
-        let mut disasters: Vec<Card> = setting
-            .clone()
-            .disasters
+        let (catastrophes, disasters): (Vec<&Disaster>, Vec<&Disaster>) =
+            setting.disasters.iter().partition(|d| d.catastrophe);
+        let finale = if setting.num_disasters > 0 {
+            catastrophes.into_iter().choose(rng).cloned()
+        } else {
+            None
+        };
+        let disasters: Vec<Disaster> = disasters
             .into_iter()
-            .choose_multiple(&mut rng, setting.num_disasters as usize)
+            .choose_multiple(
+                rng,
+                setting.num_disasters as usize - finale.is_some() as usize,
+            )
             .into_iter()
-            .map(|d| Card::Disaster(d))
+            .cloned()
             .collect();
-        deck.append(&mut disasters);
-        deck.shuffle(&mut rng);
+        let mut deck = match setting.disaster_order {
+            DisasterOrder::Shuffled => {
+                let mut deck: Vec<Card> = deck
+                    .into_iter()
+                    .map(Card::Room)
+                    .chain(disasters.into_iter().map(Card::Disaster))
+                    .collect();
+                deck.shuffle(rng);
+                deck
+            }
+            DisasterOrder::Escalating => escalating_deck(&setting, deck, disasters, rng),
+        };
+        // The catastrophe is the bottom card, drawn once every room has been dealt
+        if let Some(finale) = finale {
+            deck.insert(0, Card::Disaster(finale));
+        }
         deck.append(&mut safe);
         let mut shop = Vec::new();
         for _ in 0..setting.num_shop as usize {
@@ -81,42 +354,68 @@ impl GameState {
             .thrones
             .clone()
             .into_iter()
-            .choose_multiple(&mut rng, players.len());
+            .choose_multiple(rng, players.len());
         let mut castles = BTreeMap::new();
         let mut turn_order = Vec::new();
         for secret in players {
             castles.insert(secret.clone(), Castle::new(thrones.pop().unwrap()));
             turn_order.push(secret);
         }
-        turn_order.shuffle(&mut rng);
-        GameState {
+        turn_order.shuffle(rng);
+        Ok(GameState {
             castles,
             shop,
             discard: Vec::new(),
             previous_disasters: Vec::new(),
             queued_disasters: Vec::new(),
+            forecasts: Vec::new(),
             deck,
             turn_order,
             turn_index: 0,
+            events: Vec::new(),
+            action_seq: 0,
+            power_disabled_round: None,
+            resigned: BTreeSet::new(),
+            timeouts: BTreeMap::new(),
+            abort_votes: BTreeSet::new(),
+            aborted: false,
             round: 0,
             setting,
-        }
+        })
     }
     pub fn to_schrodinger(&self) -> SchrodingerGameState {
         let mut new_turn_order = Vec::new();
         let mut new_castles = BTreeMap::new();
-        let mut possible_rooms = self.setting.rooms.clone();
-        for room in self.discard.iter() {
-            possible_rooms.remove(room);
+        let mut possible_rooms: Vec<RoomCopies> = self
+            .setting
+            .rooms
+            .iter()
+            .map(|room| RoomCopies {
+                room: room.clone(),
+                copies: self.setting.copies_of(room),
+            })
+            .collect();
+        // Every room out of the deck, in any rotation, uses up one copy
+        let mut see = |room: &Room| {
+            if let Some(left) = possible_rooms
+                .iter_mut()
+                .find(|r| r.copies > 0 && rotation_from(&r.room, room).is_some())
+            {
+                left.copies -= 1;
+            }
+        };
+        for room in self.discard.iter().chain(self.shop.iter()) {
+            see(room);
         }
         for (index, secret) in self.turn_order.iter().enumerate() {
-            new_turn_order.push(index.to_string());
-            let castle = self.castles.get(secret).unwrap().clone();
+            new_turn_order.push(PlayerSecret::from(index.to_string()));
+            let castle = self.castles.get(secret).unwrap();
             for room in castle.rooms.values() {
-                possible_rooms.remove(room);
+                see(room);
             }
-            new_castles.insert(index.to_string(), castle);
+            new_castles.insert(PlayerSecret::from(index.to_string()), castle.clone());
         }
+        possible_rooms.retain(|r| r.copies > 0);
         let mut possible_disasters = self.setting.disasters.clone();
         for disaster in self.previous_disasters.iter() {
             possible_disasters.remove(disaster);
@@ -124,6 +423,9 @@ impl GameState {
         for disaster in self.queued_disasters.iter() {
             possible_disasters.remove(disaster);
         }
+        for forecast in self.forecasts.iter() {
+            possible_disasters.remove(&forecast.disaster);
+        }
         SchrodingerGameState {
             castles: new_castles,
             shop: self.shop.clone(),
@@ -131,14 +433,16 @@ impl GameState {
             possible_rooms,
             previous_disasters: self.previous_disasters.clone(),
             queued_disasters: self.queued_disasters.clone(),
+            forecasts: self.forecasts.clone(),
             possible_disasters,
             turn_order: new_turn_order,
             turn_index: self.turn_index,
             round: self.round,
             setting: self.setting.clone(),
+            power_disabled_round: self.power_disabled_round,
         }
     }
-    pub fn possible_actions(&self, player_secret: &str) -> Vec<Action> {
+    pub fn possible_actions(&self, player_secret: &PlayerSecret) -> Vec<Action> {
         if let Some(castle) = self.castles.get(player_secret) {
             if self.is_turn_player(player_secret) {
                 return castle.possible_actions(&self.shop);
@@ -146,7 +450,206 @@ impl GameState {
         }
         return Vec::new();
     }
-    pub fn action(&self, player_secret: &str, action: Action) -> Result<GameState> {
+    /// Apply `action` for the player, provided `expected_seq` matches the number of actions
+    /// applied so far. Clients echo the last sequence number they saw so that an action
+    /// decided on an outdated state is rejected instead of misapplied.
+    #[cfg(feature = "std")]
+    pub fn action(
+        &self,
+        player_secret: &PlayerSecret,
+        expected_seq: u64,
+        action: Action,
+    ) -> Result<GameState> {
+        self.action_with_rng(player_secret, expected_seq, action, &mut thread_rng())
+    }
+    pub fn action_with_rng<R: Rng + ?Sized>(
+        &self,
+        player_secret: &PlayerSecret,
+        expected_seq: u64,
+        action: Action,
+        rng: &mut R,
+    ) -> Result<GameState> {
+        #[cfg(feature = "log")]
+        let _span = tracing::debug_span!(
+            "action",
+            seq = self.action_seq,
+            seat = ?self.get_player_turn_index(player_secret),
+            action = ?action
+        )
+        .entered();
+        if expected_seq != self.action_seq {
+            log_debug!(expected_seq, "stale action rejected");
+            return Err(GameError::StaleAction {
+                expected_seq,
+                current_seq: self.action_seq,
+            });
+        }
+        let mut game = match self.apply_action(player_secret, action, rng) {
+            Ok(game) => game,
+            Err(e) => {
+                log_debug!(error = %e, "action rejected");
+                return Err(e);
+            }
+        };
+        game.timeouts.remove(player_secret);
+        game.action_seq += 1;
+        log_debug!(round = game.round, "action applied");
+        Ok(game)
+    }
+    /// Withdraw the player from the game. They leave the turn order, take no more damage
+    /// and cannot win, while their castle stays on the table.
+    #[cfg(feature = "std")]
+    pub fn resign(&self, player_secret: &PlayerSecret) -> Result<GameState> {
+        self.resign_with_rng(player_secret, &mut thread_rng())
+    }
+    pub fn resign_with_rng<R: Rng + ?Sized>(
+        &self,
+        player_secret: &PlayerSecret,
+        rng: &mut R,
+    ) -> Result<GameState> {
+        if !self.is_player(player_secret) || self.is_resigned(player_secret) {
+            return Err(GameError::InvalidPlayer);
+        }
+        let mut game = self.clone();
+        let mut round_over = false;
+        if let Some(index) = game.turn_order.iter().position(|s| s == player_secret) {
+            game.turn_order.remove(index);
+            if index < game.turn_index {
+                game.turn_index -= 1;
+            }
+            if game.turn_index >= game.turn_order.len() {
+                // The last player of the round resigned on their turn
+                round_over = true;
+                game.turn_index = 0;
+            }
+        }
+        game.castles.get_mut(player_secret).unwrap().damage = 0;
+        game.resigned.insert(player_secret.clone());
+        game.events.push(GameEvent::PlayerResigned {
+            round: game.round,
+            seat: game.get_seat(player_secret).unwrap(),
+        });
+        // Their discards may have been all a queued disaster was waiting for
+        game.resume_queued_disaster(rng);
+        if round_over && !game.is_over() {
+            game.turn_index = game.turn_order.len() - 1;
+            game = game.next_turn_with_rng(rng);
+        }
+        game.action_seq += 1;
+        log_info!(seat = game.get_seat(player_secret), "player resigned");
+        Ok(game)
+    }
+    /// Play the default move for the turn player whose time ran out: while they owe damage,
+    /// discard a room, preferring removable ones and the least treasure, otherwise pass the
+    /// turn. Fails with `NoDefaultMove` when they owe damage but can discard nothing.
+    #[cfg(feature = "std")]
+    pub fn time_out(&self, player_secret: &PlayerSecret) -> Result<GameState> {
+        self.time_out_with_rng(player_secret, &mut thread_rng())
+    }
+    pub fn time_out_with_rng<R: Rng + ?Sized>(
+        &self,
+        player_secret: &PlayerSecret,
+        rng: &mut R,
+    ) -> Result<GameState> {
+        if !self.is_player(player_secret) {
+            return Err(GameError::InvalidPlayer);
+        } else if !self.is_turn_player(player_secret) {
+            return Err(GameError::NotTurnPlayer);
+        }
+        let mut game = self.clone();
+        game.events.push(GameEvent::TimedOut {
+            round: game.round,
+            seat: game.get_seat(player_secret).unwrap(),
+        });
+        let timeouts = game.timeouts.entry(player_secret.clone()).or_insert(0);
+        *timeouts = timeouts.saturating_add(1);
+        let castle = &self.castles[player_secret];
+        let mut game = if castle.damage == 0 {
+            game.next_turn_with_rng(rng)
+        } else {
+            let removable = castle.removable_rooms();
+            let mut candidates: Vec<(Pos, &Room)> =
+                castle.iter().filter(|(_, room)| !room.throne).collect();
+            candidates.sort_by_key(|(pos, room)| (!removable.contains(pos), room.treasure, *pos));
+            candidates
+                .into_iter()
+                .find_map(|(pos, _)| {
+                    game.apply_action(player_secret, Action::Discard(pos.into()), rng)
+                        .ok()
+                })
+                .ok_or(GameError::NoDefaultMove)?
+        };
+        game.action_seq += 1;
+        log_info!(seat = game.get_seat(player_secret), "turn timed out");
+        Ok(game)
+    }
+    /// Vote to scrap the game. Once as many players still in the game as the setting's
+    /// `abort_votes_needed` have voted, it ends with no winner. Voting again changes nothing.
+    pub fn vote_abort(&self, player_secret: &PlayerSecret) -> Result<GameState> {
+        if !self.is_player(player_secret) || self.is_resigned(player_secret) {
+            return Err(GameError::InvalidPlayer);
+        }
+        let mut game = self.clone();
+        if self.is_over() || !game.abort_votes.insert(player_secret.clone()) {
+            return Ok(game);
+        }
+        let votes = game
+            .abort_votes
+            .iter()
+            .filter(|s| !game.is_resigned(s))
+            .count();
+        let active = game.castles.len() - game.resigned.len();
+        let needed = match game.setting.abort_votes_needed {
+            Some(needed) => (needed as usize).min(active),
+            None => active / 2 + 1,
+        };
+        if votes >= needed {
+            log_info!(votes, "abort vote passed");
+            return Ok(game.abort());
+        }
+        game.action_seq += 1;
+        Ok(game)
+    }
+    /// End the game at once with no winner, such as an operator scrapping a stuck game.
+    pub fn abort(&self) -> GameState {
+        let mut game = self.clone();
+        if game.is_over() {
+            return game;
+        }
+        game.aborted = true;
+        game.events
+            .push(GameEvent::GameAborted { round: game.round });
+        game.action_seq += 1;
+        log_info!(round = game.round, "game aborted");
+        game
+    }
+    /// Place `room` from the shop at `pos`, looking the room up by value rather than by
+    /// shop index so that purchases by other players cannot shift the selection.
+    #[cfg(feature = "std")]
+    pub fn place_from_shop(
+        &self,
+        player_secret: &PlayerSecret,
+        expected_seq: u64,
+        room: &Room,
+        pos: Pos,
+    ) -> Result<GameState> {
+        let action = self.place_action(room, pos)?;
+        self.action(player_secret, expected_seq, action)
+    }
+    /// The `Action::Place` buying `room` from the current shop.
+    pub fn place_action(&self, room: &Room, pos: Pos) -> Result<Action> {
+        let index = self.shop_index_of(room).ok_or(GameError::RoomNotInShop)?;
+        Ok(Action::Place(index, pos.into()))
+    }
+    pub fn shop_index_of(&self, room: &Room) -> Option<usize> {
+        self.shop.iter().position(|r| r == room)
+    }
+    fn apply_action<R: Rng + ?Sized>(
+        &self,
+        player_secret: &PlayerSecret,
+        action: Action,
+        rng: &mut R,
+    ) -> Result<GameState> {
         if !self.castles.contains_key(player_secret) {
             return Err(GameError::InvalidPlayer);
         } else if !self.is_turn_player(player_secret) {
@@ -155,73 +658,85 @@ impl GameState {
         match action {
             Action::Place(index, pos) => {
                 if index >= self.shop.len() {
-                    return Err(GameError::InvalidShopIndex);
+                    return Err(GameError::InvalidShopIndex {
+                        index,
+                        shop_len: self.shop.len(),
+                    });
                 }
                 let mut game = self.clone();
                 let room = game.shop.remove(index);
                 game.castles.insert(
-                    player_secret.to_string(),
+                    player_secret.clone(),
                     game.castles
                         .get(player_secret)
                         .unwrap()
-                        .place_room(room, pos)?,
+                        .place_room(room, pos)
+                        .map_err(|e| GameError::castle(&action, e))?,
                 );
-                game = game.next_turn();
+                game = game.next_turn_with_rng(rng);
                 Ok(game)
             }
             Action::Move(from, to) => {
                 let mut game = self.clone();
                 game.castles.insert(
-                    player_secret.to_string(),
+                    player_secret.clone(),
                     game.castles
                         .get(player_secret)
                         .unwrap()
-                        .move_room(from, to)?,
+                        .move_room(from, to)
+                        .map_err(|e| GameError::castle(&action, e))?,
                 );
-                game = game.next_turn();
+                game = game.next_turn_with_rng(rng);
                 Ok(game)
             }
             Action::Swap(pos1, pos2) => {
                 let mut game = self.clone();
                 game.castles.insert(
-                    player_secret.to_string(),
+                    player_secret.clone(),
                     game.castles
                         .get(player_secret)
                         .unwrap()
-                        .swap_room(pos1, pos2)?,
+                        .swap_room(pos1, pos2)
+                        .map_err(|e| GameError::castle(&action, e))?,
                 );
-                game = game.next_turn();
+                game = game.next_turn_with_rng(rng);
                 Ok(game)
             }
             Action::Discard(pos) => {
                 let mut game = self.clone();
-                let (castle, room) = game.castles[player_secret].discard_room(pos)?;
+                let (castle, room) = game.castles[player_secret]
+                    .discard_room(pos)
+                    .map_err(|e| GameError::castle(&action, e))?;
                 game.discard.push(room);
-                game.castles.insert(player_secret.to_string(), castle);
+                game.castles.insert(player_secret.clone(), castle);
                 game.sweep_lost_castles();
-                if game.castles.values().all(|c| c.damage == 0 || c.is_lost())
-                    && game.queued_disasters.len() > 0
-                {
-                    let disaster = game.queued_disasters.pop().unwrap();
-                    game = game.resolve_disaster(disaster);
-                }
+                game.resume_queued_disaster(rng);
                 Ok(game)
             }
         }
     }
+    #[cfg(feature = "std")]
     pub fn next_turn(&self) -> GameState {
+        self.next_turn_with_rng(&mut thread_rng())
+    }
+    pub fn next_turn_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> GameState {
         let mut game = self.clone();
         game.turn_index += 1;
         if game.turn_index >= game.turn_order.len() {
             game.turn_index = 0;
             game.turn_order.rotate_left(1);
-            game = game.next_round()
+            game = game.next_round_with_rng(rng)
         }
         game
     }
+    #[cfg(feature = "std")]
     pub fn next_round(&self) -> GameState {
+        self.next_round_with_rng(&mut thread_rng())
+    }
+    pub fn next_round_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> GameState {
         let mut game = self.clone();
         game.round += 1;
+        log_info!(round = game.round, deck = game.deck.len(), "round starts");
         game.discard.append(&mut game.shop);
         let mut disasters = Vec::new();
         let mut redealt = false;
@@ -235,52 +750,169 @@ impl GameState {
                 }
             }
             if !redealt && disasters.len() > 1 {
-                let mut card_disasters = disasters
+                let card_disasters = disasters
                     .drain(..disasters.len() - 1)
                     .map(|d| Card::Disaster(d))
                     .collect();
-                game.deck.append(&mut card_disasters);
-                game.deck.shuffle(&mut thread_rng());
+                game.return_to_deck(card_disasters, rng);
                 redealt = true;
             }
         }
+        let mut disasters = rules::due_disasters(
+            disasters,
+            &mut game.forecasts,
+            game.setting.forecast_rounds,
+            game.round,
+        );
         if let Some(disaster) = disasters.pop() {
-            game = game.resolve_disaster(disaster);
+            game = game.resolve_disaster(disaster, rng);
             game.queued_disasters = disasters;
         }
+        game.announce_catastrophe();
         game
     }
-    fn sweep_lost_castles(&mut self) {
-        let mut turn_order = Vec::new();
-        for (index, secret) in self.turn_order.iter().enumerate() {
-            let castle = &self.castles[secret];
-            if !castle.is_lost() {
-                turn_order.push(secret.to_string());
-            } else {
-                if index < self.turn_index {
-                    self.turn_index -= 1;
+    /// Shuffle `cards` back into the deck, keeping a catastrophe at the bottom. Escalating
+    /// decks are not reshuffled, the cards are inserted at random depths instead.
+    fn return_to_deck<R: Rng + ?Sized>(&mut self, cards: Vec<Card>, rng: &mut R) {
+        let finale = match self.deck.first() {
+            Some(Card::Disaster(d)) if d.catastrophe => Some(self.deck.remove(0)),
+            _ => None,
+        };
+        match self.setting.disaster_order {
+            DisasterOrder::Shuffled => {
+                self.deck.extend(cards);
+                self.deck.shuffle(rng);
+            }
+            DisasterOrder::Escalating => {
+                for card in cards {
+                    let index = rng.gen_range(0..=self.deck.len());
+                    self.deck.insert(index, card);
                 }
             }
         }
-        if self.turn_index >= turn_order.len() {
-            self.turn_index = 0;
+        if let Some(finale) = finale {
+            self.deck.insert(0, finale);
+        }
+    }
+    /// Log the catastrophe at the bottom of the deck once the next shop refill is bound to
+    /// reach it, that is when at most a shop's worth of rooms is left above it.
+    fn announce_catastrophe(&mut self) {
+        let finale = match self.deck.first() {
+            Some(Card::Disaster(d)) if d.catastrophe => d.name.clone(),
+            _ => return,
+        };
+        let rooms_left = self
+            .deck
+            .iter()
+            .filter(|c| matches!(c, Card::Room(_)))
+            .count();
+        let announced = self
+            .events
+            .iter()
+            .any(|e| matches!(e, GameEvent::CatastropheAnnounced { .. }));
+        if rooms_left <= self.setting.num_shop as usize && !announced {
+            self.events.push(GameEvent::CatastropheAnnounced {
+                round: self.round,
+                disaster: finale,
+            });
+        }
+    }
+    /// The parts of the game disasters act on, sparing resigned players.
+    fn table(&mut self) -> rules::Table<'_> {
+        rules::Table {
+            setting: &self.setting,
+            round: self.round,
+            spared: &self.resigned,
+            castles: &mut self.castles,
+            shop: &mut self.shop,
+            discard: &mut self.discard,
+            turn_order: &mut self.turn_order,
+            turn_index: &mut self.turn_index,
+            previous_disasters: &mut self.previous_disasters,
+            power_disabled_round: &mut self.power_disabled_round,
         }
-        self.turn_order = turn_order;
     }
-    fn resolve_disaster(&self, disaster: Disaster) -> GameState {
+    fn sweep_lost_castles(&mut self) {
+        let lost = self.table().sweep_lost_castles();
+        self.log_lost(&lost);
+    }
+    /// Lost castles leave the turn order when swept, so each is only reported once.
+    #[cfg_attr(not(feature = "log"), allow(unused_variables))]
+    fn log_lost(&self, lost: &[PlayerSecret]) {
+        for secret in lost {
+            log_info!(
+                round = self.round,
+                seat = self.get_seat(secret),
+                "castle lost"
+            );
+        }
+    }
+    /// Resolve the queued disaster once no castle owes damage any more.
+    fn resume_queued_disaster<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        if self.castles.values().all(|c| c.damage == 0 || c.is_lost())
+            && !self.queued_disasters.is_empty()
+        {
+            let disaster = self.queued_disasters.pop().unwrap();
+            *self = self.resolve_disaster(disaster, rng);
+        }
+    }
+    fn resolve_disaster<R: Rng + ?Sized>(&self, disaster: Disaster, rng: &mut R) -> GameState {
+        log_info!(round = self.round, disaster = %disaster.name, "disaster strikes");
         let mut game = self.clone();
-        let diamond = disaster.diamond_damage(game.previous_disasters.len() as u8);
-        let cross = disaster.cross_damage(game.previous_disasters.len() as u8);
-        let moon = disaster.moon_damage(game.previous_disasters.len() as u8);
-        for castle in game.castles.values_mut() {
-            *castle = castle.deal_damage(diamond, cross, moon);
-        }
-        game.sweep_lost_castles();
-        game.previous_disasters.push(disaster);
+        let resolution = game.table().resolve_disaster(disaster, rng);
+        for (seat, absorbed) in resolution.absorbed {
+            game.events.push(GameEvent::DamageMitigated {
+                round: game.round,
+                seat,
+                diamond: absorbed[0],
+                cross: absorbed[1],
+                moon: absorbed[2],
+            });
+        }
+        game.log_lost(&resolution.lost);
         game
     }
 }
 
+/// Deck dealing `rooms` with one of `disasters` shuffled into each of as many equal slices,
+/// the mildest disaster in the first slice dealt.
+fn escalating_deck<R: Rng + ?Sized>(
+    setting: &GameSetting,
+    rooms: Vec<Room>,
+    mut disasters: Vec<Disaster>,
+    rng: &mut R,
+) -> Vec<Card> {
+    disasters.sort_by_key(|d| setting.severity(d));
+    let slices = disasters.len().max(1);
+    let mut disasters = disasters.into_iter();
+    let mut deck = Vec::new();
+    for i in 0..slices {
+        let mut slice: Vec<Card> = rooms[i * rooms.len() / slices..(i + 1) * rooms.len() / slices]
+            .iter()
+            .map(|r| Card::Room(r.clone()))
+            .chain(disasters.next().map(Card::Disaster))
+            .collect();
+        slice.shuffle(rng);
+        deck.append(&mut slice);
+    }
+    // Cards are dealt from the back
+    deck.reverse();
+    deck
+}
+
+/// Split diamond, cross and moon damage into what is dealt and what `shield` absorbs, using
+/// the shield up on each kind in turn.
+fn mitigate(mut shield: u8, damage: [u8; 3]) -> ([u8; 3], [u8; 3]) {
+    let mut dealt = damage;
+    let mut absorbed = [0; 3];
+    for (dealt, absorbed) in dealt.iter_mut().zip(absorbed.iter_mut()) {
+        *absorbed = shield.min(*dealt);
+        *dealt -= *absorbed;
+        shield -= *absorbed;
+    }
+    (dealt, absorbed)
+}
+
 fn compare_game_state(a: &Castle, b: &Castle) -> Ordering {
     if !a.is_lost() && b.is_lost() {
         return Ordering::Greater;
@@ -313,28 +945,71 @@ fn compare_game_state(a: &Castle, b: &Castle) -> Ordering {
 
 impl GameState {
     pub fn is_over(&self) -> bool {
-        self.turn_order.len() <= 1
+        self.aborted
+            || self.turn_order.len() <= 1
             || self.previous_disasters.len() == self.setting.num_disasters as usize
     }
-    pub fn is_victorious(&self, secret: &str) -> bool {
-        let mut castles: Vec<(&String, &Castle)> = self.castles.iter().collect();
+    pub fn is_victorious(&self, secret: &PlayerSecret) -> bool {
+        if self.aborted {
+            return false;
+        }
+        let mut castles: Vec<(&PlayerSecret, &Castle)> = self
+            .castles
+            .iter()
+            .filter(|(s, _)| !self.is_resigned(s))
+            .collect();
         castles.sort_unstable_by(|(_, a), (_, b)| compare_game_state(b, a)); // Reversed for descending order
         let winner = castles.first();
         if winner.is_none() {
             return false;
         }
         let winner = winner.unwrap().clone();
-        let winners: Vec<(&String, &Castle)> = castles
+        let winners: Vec<(&PlayerSecret, &Castle)> = castles
             .into_iter()
             .clone()
             .filter(|(_, castle)| matches!(compare_game_state(castle, winner.1), Ordering::Equal))
             .collect();
         winners.iter().any(|(s, _)| s == &secret)
     }
-    pub fn is_player(&self, secret: &str) -> bool {
+    /// Seats grouped by place, best first, with tied players sharing a place. Players who
+    /// resigned share the last place.
+    pub fn get_standings(&self) -> Vec<Vec<usize>> {
+        let mut castles: Vec<(usize, &Castle)> = self
+            .castles
+            .iter()
+            .enumerate()
+            .filter(|(_, (s, _))| !self.is_resigned(s))
+            .map(|(seat, (_, castle))| (seat, castle))
+            .collect();
+        castles.sort_by(|(_, a), (_, b)| compare_game_state(b, a)); // Reversed for descending order
+        let mut standings: Vec<Vec<usize>> = Vec::new();
+        let mut previous: Option<&Castle> = None;
+        for (seat, castle) in castles {
+            match (previous, standings.last_mut()) {
+                (Some(p), Some(place)) if compare_game_state(p, castle) == Ordering::Equal => {
+                    place.push(seat)
+                }
+                _ => standings.push(vec![seat]),
+            }
+            previous = Some(castle);
+        }
+        let resigned: Vec<usize> = self
+            .resigned
+            .iter()
+            .filter_map(|s| self.get_seat(s))
+            .collect();
+        if !resigned.is_empty() {
+            standings.push(resigned);
+        }
+        standings
+    }
+    pub fn is_player(&self, secret: &PlayerSecret) -> bool {
         self.castles.contains_key(secret)
     }
-    pub fn is_turn_player(&self, secret: &str) -> bool {
+    pub fn is_turn_player(&self, secret: &PlayerSecret) -> bool {
+        if self.aborted {
+            return false;
+        }
         // Check if player need discard
         if let Some(castle) = self.castles.get(secret) {
             if castle.damage > 0 && !castle.is_lost() {
@@ -346,9 +1021,491 @@ impl GameState {
             return false;
         }
         // Check if it is player's turn
-        self.turn_order.len() > 0 && self.turn_order[self.turn_index] == secret
+        self.turn_order.len() > 0 && self.turn_order[self.turn_index] == *secret
+    }
+    pub fn is_resigned(&self, secret: &PlayerSecret) -> bool {
+        self.resigned.contains(secret)
+    }
+    pub fn is_aborted(&self) -> bool {
+        self.aborted
+    }
+    /// Turns in a row the player let time out, so a host can treat them as away from the
+    /// keyboard and resign them after too many.
+    pub fn get_timeouts(&self, secret: &PlayerSecret) -> u8 {
+        self.timeouts.get(secret).copied().unwrap_or(0)
+    }
+    pub fn get_action_seq(&self) -> u64 {
+        self.action_seq
+    }
+    /// Events after the first `since`, so a client polling the log can pass the number of
+    /// events it has already seen.
+    pub fn get_events_since(&self, since: usize) -> &[GameEvent] {
+        self.events.get(since..).unwrap_or(&[])
     }
-    pub fn get_player_turn_index(&self, secret: &str) -> Option<usize> {
+    /// Whether a disaster has disabled powered room bonuses for the current round.
+    pub fn is_power_disabled(&self) -> bool {
+        self.power_disabled_round == Some(self.round)
+    }
+    pub fn get_player_turn_index(&self, secret: &PlayerSecret) -> Option<usize> {
         self.turn_order.iter().position(|s| s == secret)
     }
 }
+
+#[cfg(test)]
+pub(crate) fn test_game(players: &[&str]) -> GameState {
+    use crate::{load_disasters_from_str, load_rooms_from_str, load_thrones_from_str};
+    use rand::{rngs::StdRng, SeedableRng};
+    let setting = GameSetting::base(
+        load_thrones_from_str(include_str!("../../thrones.ron")).unwrap(),
+        load_rooms_from_str(include_str!("../../rooms.ron")).unwrap(),
+        load_disasters_from_str(include_str!("../../disasters.ron")).unwrap(),
+    );
+    GameState::new_with_rng(
+        players.iter().map(|&p| PlayerSecret::from(p)).collect(),
+        setting,
+        &mut StdRng::seed_from_u64(0),
+    )
+    .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        card::Card, mitigate, test_game, DisasterOrder, GameError, GameEvent, GameState,
+        PlayerSecret, SettingError,
+    };
+    use crate::{
+        disaster::{DamageCalculation, Disaster, Effect},
+        room::{Ability, RoomCard, RoomExt},
+    };
+    use disastle_castle_rust::Action;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_deck_expands_copies() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a", "b"]);
+        let cards: Vec<RoomCard> = game
+            .setting
+            .rooms
+            .iter()
+            .take(2)
+            .enumerate()
+            .map(|(i, room)| RoomCard {
+                copies: i as u8 * 3,
+                ..RoomCard::new(i.to_string(), room.clone())
+            })
+            .collect();
+        let setting = game.setting.clone().with_cards(&cards);
+        assert_eq!(setting.copies_of(&cards[0].room), 0);
+        assert_eq!(setting.copies_of(&cards[1].room), 3);
+        let players = vec![PlayerSecret::from("a"), PlayerSecret::from("b")];
+        let expanded = GameState::new_with_rng(players, setting, &mut rng).unwrap();
+        let total = |g: &GameState| g.deck.len() + g.shop.len();
+        assert_eq!(total(&expanded), total(&game) + 1);
+    }
+
+    #[test]
+    fn test_schrodinger_counts_copies() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a"]);
+        let cards: Vec<RoomCard> = game
+            .setting
+            .rooms
+            .iter()
+            .take(2)
+            .enumerate()
+            .map(|(i, room)| RoomCard {
+                copies: if i == 0 { 0 } else { 4 },
+                ..RoomCard::new(i.to_string(), room.clone())
+            })
+            .collect();
+        let setting = game.setting.clone().with_cards(&cards);
+        let players = vec![PlayerSecret::from("a"), PlayerSecret::from("b")];
+        let game = GameState::new_with_rng(players, setting, &mut rng).unwrap();
+        let schrodinger = game.to_schrodinger();
+        // Exactly the rooms left in the deck remain possible
+        for left in schrodinger.possible_rooms.iter() {
+            let in_deck = game
+                .deck
+                .iter()
+                .filter(|c| matches!(c, Card::Room(r) if r == &left.room))
+                .count();
+            assert_eq!(left.copies as usize, in_deck);
+        }
+        let rooms_in_deck = game
+            .deck
+            .iter()
+            .filter(|c| matches!(c, Card::Room(_)))
+            .count();
+        let possible: usize = schrodinger
+            .possible_rooms
+            .iter()
+            .map(|r| r.copies as usize)
+            .sum();
+        assert_eq!(possible, rooms_in_deck);
+        assert!(schrodinger
+            .possible_rooms
+            .iter()
+            .all(|r| r.room != cards[0].room));
+
+        // An empty deck ends the deal instead of panicking
+        let mut empty = schrodinger;
+        empty.possible_rooms.clear();
+        empty.setting.num_safe = u8::MAX;
+        assert!(empty.next_round_with_rng(&mut rng).shop.is_empty());
+    }
+
+    #[test]
+    fn test_validate_setting() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut setting = test_game(&["a"]).setting;
+        assert_eq!(setting.validate(4), Ok(()));
+        setting.num_shop = 6;
+        assert_eq!(
+            setting.validate(11),
+            Err(vec![
+                SettingError::ShopLargerThanSafe {
+                    num_shop: 6,
+                    num_safe: 5
+                },
+                SettingError::NotEnoughThrones {
+                    needed: 11,
+                    available: 10
+                },
+            ])
+        );
+        let players = (0..11).map(|n| PlayerSecret::from(n.to_string())).collect();
+        assert_eq!(
+            GameState::new_with_rng(players, setting, &mut rng)
+                .unwrap_err()
+                .len(),
+            2
+        );
+
+        // Only one of several catastrophes can be dealt
+        let mut setting = test_game(&["a"]).setting;
+        setting.disasters = setting
+            .disasters
+            .into_iter()
+            .enumerate()
+            .map(|(i, d)| Disaster {
+                catastrophe: i < 8,
+                ..d
+            })
+            .collect();
+        assert_eq!(
+            setting.validate(2),
+            Err(vec![SettingError::NotEnoughDisasters {
+                needed: 6,
+                available: 5
+            }])
+        );
+    }
+
+    #[test]
+    fn test_resign() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a", "b", "c"]);
+        let a = PlayerSecret::from("a");
+        let resigned = game.resign_with_rng(&a, &mut rng).unwrap();
+        assert!(resigned.is_resigned(&a));
+        assert!(!resigned.turn_order.contains(&a));
+        assert_eq!(resigned.get_action_seq(), game.get_action_seq() + 1);
+        assert!(matches!(
+            resigned.events.last(),
+            Some(GameEvent::PlayerResigned { seat: 0, .. })
+        ));
+        assert!(!resigned.is_victorious(&a));
+        assert!(resigned.resign_with_rng(&a, &mut rng).is_err());
+
+        let over = resigned
+            .resign_with_rng(&PlayerSecret::from("b"), &mut rng)
+            .unwrap();
+        assert!(over.is_over());
+        assert!(over.is_victorious(&PlayerSecret::from("c")));
+        assert_eq!(over.get_standings(), vec![vec![2], vec![0, 1]]);
+        let summary = over.summary();
+        assert_eq!(summary.winners, vec![2]);
+        assert_eq!(summary.resigned, vec![0, 1]);
+
+        // Disasters leave the castles of resigned players alone
+        let first = game.turn_order[game.turn_index].clone();
+        let place = game
+            .possible_actions(&first)
+            .into_iter()
+            .find(|action| matches!(action, Action::Place(..)))
+            .unwrap();
+        let built = game
+            .action_with_rng(&first, 0, place, &mut rng)
+            .unwrap()
+            .resign_with_rng(&first, &mut rng)
+            .unwrap();
+        let disaster = harmless_disaster(&built, vec![Effect::RemoveOuterRoom]);
+        let resolved = built.resolve_disaster(disaster, &mut rng);
+        assert_eq!(resolved.castles[&first], built.castles[&first]);
+
+        // The last player of the round resigning ends the round
+        let mut last_turn = game.clone();
+        last_turn.turn_index = last_turn.turn_order.len() - 1;
+        last_turn.shop.pop();
+        last_turn.deck.retain(|c| matches!(c, Card::Room(_)));
+        let last = last_turn.turn_order[last_turn.turn_index].clone();
+        let next = last_turn.resign_with_rng(&last, &mut rng).unwrap();
+        assert_eq!(next.round, game.round + 1);
+        assert_eq!(next.shop.len(), game.setting.num_shop as usize);
+        assert_eq!(next.turn_index, 0);
+    }
+
+    #[test]
+    fn test_time_out() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a", "b"]);
+        let (first, second) = (game.turn_order[0].clone(), game.turn_order[1].clone());
+        assert!(matches!(
+            game.time_out_with_rng(&second, &mut rng),
+            Err(GameError::NotTurnPlayer)
+        ));
+        let timed_out = game.time_out_with_rng(&first, &mut rng).unwrap();
+        assert!(timed_out.is_turn_player(&second));
+        assert_eq!(timed_out.get_action_seq(), game.get_action_seq() + 1);
+        assert!(matches!(
+            timed_out.events.last(),
+            Some(GameEvent::TimedOut { seat, .. }) if Some(*seat) == game.get_seat(&first)
+        ));
+        assert_eq!(timed_out.get_timeouts(&first), 1);
+
+        let mut game = game;
+        game.timeouts.insert(first.clone(), 2);
+        let action = game.possible_actions(&first)[0].clone();
+        let acted = game
+            .action_with_rng(&first, game.get_action_seq(), action, &mut rng)
+            .unwrap();
+        assert_eq!(acted.get_timeouts(&first), 0);
+
+        // Owing damage, the player discards instead of passing the turn
+        let mut damaged = acted;
+        damaged.castles.get_mut(&first).unwrap().damage = 1;
+        let discarded = damaged.time_out_with_rng(&first, &mut rng).unwrap();
+        assert_eq!(discarded.discard.len(), damaged.discard.len() + 1);
+        assert_eq!(discarded.turn_index, damaged.turn_index);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_place_from_shop() {
+        use crate::{castle::CastleExt, pos::Pos};
+        let game = test_game(&["a", "b", "c"]);
+        let place_at = |game: &GameState, player: &PlayerSecret, index: usize| {
+            game.possible_actions(player)
+                .into_iter()
+                .find_map(|action| match action {
+                    Action::Place(i, pos) if i == index => Some(Pos::from(pos)),
+                    _ => None,
+                })
+                .unwrap()
+        };
+        let first = game.turn_order[game.turn_index].clone();
+        let wanted = game.shop[2].clone();
+        let pos = place_at(&game, &first, 0);
+        let bought = game
+            .action(&first, 0, Action::Place(0, pos.into()))
+            .unwrap();
+        // The purchase shifted the wanted room, so its old index now buys another room
+        assert_eq!(bought.shop_index_of(&wanted), Some(1));
+        let second = bought.turn_order[bought.turn_index].clone();
+        let pos = place_at(&bought, &second, 1);
+        assert!(matches!(
+            bought.place_action(&wanted, pos),
+            Ok(Action::Place(1, _))
+        ));
+        let seq = bought.get_action_seq();
+        let placed = bought.place_from_shop(&second, seq, &wanted, pos).unwrap();
+        assert_eq!(placed.castles[&second].room_at(pos), Some(&wanted));
+        assert_eq!(placed.shop_index_of(&wanted), None);
+
+        let third = placed.turn_order[placed.turn_index].clone();
+        let seq = placed.get_action_seq();
+        assert!(matches!(
+            placed.place_action(&wanted, pos),
+            Err(GameError::RoomNotInShop)
+        ));
+        assert!(matches!(
+            placed.place_from_shop(&third, seq, &wanted, pos),
+            Err(GameError::RoomNotInShop)
+        ));
+    }
+
+    #[test]
+    fn test_vote_abort() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a", "b", "c"]);
+        let (a, b) = (PlayerSecret::from("a"), PlayerSecret::from("b"));
+        let voted = game.vote_abort(&a).unwrap();
+        assert!(!voted.is_over());
+        assert_eq!(voted.vote_abort(&a).unwrap(), voted);
+        let aborted = voted.vote_abort(&b).unwrap();
+        assert!(aborted.is_aborted() && aborted.is_over());
+        assert!(!aborted.is_victorious(&a) && !aborted.is_victorious(&b));
+        assert!(aborted.possible_actions(&a).is_empty());
+        assert!(matches!(
+            aborted.events.last(),
+            Some(GameEvent::GameAborted { .. })
+        ));
+        assert_eq!(aborted.abort(), aborted);
+        assert!(game.abort().is_aborted());
+
+        let mut unanimous = game.clone();
+        unanimous.setting.abort_votes_needed = Some(3);
+        let voted = unanimous.vote_abort(&a).unwrap().vote_abort(&b).unwrap();
+        assert!(!voted.is_over());
+        // The threshold shrinks to the players left
+        let left = unanimous
+            .resign_with_rng(&PlayerSecret::from("c"), &mut rng)
+            .unwrap();
+        let voted = left.vote_abort(&a).unwrap();
+        assert!(!voted.is_over());
+        assert!(voted.vote_abort(&b).unwrap().is_aborted());
+        unanimous.setting.abort_votes_needed = Some(4);
+        assert_eq!(
+            unanimous.setting.validate(3),
+            Err(vec![SettingError::InvalidAbortVotes {
+                needed: 4,
+                players: 3
+            }])
+        );
+    }
+
+    fn harmless_disaster(game: &GameState, effects: Vec<Effect>) -> Disaster {
+        let mut disaster = game.setting.disasters.iter().next().unwrap().clone();
+        let harmless = DamageCalculation {
+            multiplier: 0,
+            addition: 0,
+            formula: None,
+        };
+        disaster.diamond = harmless.clone();
+        disaster.cross = harmless.clone();
+        disaster.moon = harmless;
+        disaster.effects = effects;
+        disaster
+    }
+
+    #[test]
+    fn test_disaster_effects() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let game = test_game(&["a", "b", "c"]);
+        let disaster = harmless_disaster(
+            &game,
+            vec![
+                Effect::DiscardShop,
+                Effect::SkipNextTurn,
+                Effect::RemoveOuterRoom,
+                Effect::DisablePower,
+            ],
+        );
+        assert!(!game.is_power_disabled());
+        let resolved = game.resolve_disaster(disaster, &mut rng);
+        assert!(resolved.shop.is_empty());
+        assert_eq!(resolved.discard.len(), game.shop.len());
+        assert_eq!(resolved.turn_index, 1);
+        // Castles holding only their throne have nothing to remove
+        assert_eq!(resolved.castles, game.castles);
+        assert!(resolved.is_power_disabled());
+        assert!(!resolved.next_round_with_rng(&mut rng).is_power_disabled());
+    }
+
+    #[test]
+    fn test_catastrophe_is_last() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut setting = test_game(&["a"]).setting;
+        let mut finale = setting.disasters.iter().next().unwrap().clone();
+        setting.disasters.remove(&finale);
+        finale.catastrophe = true;
+        setting.disasters.insert(finale.clone());
+        assert_eq!(
+            finale.moon_damage(1),
+            2 * finale.moon.multiplier + finale.moon.addition
+        );
+        let num_shop = setting.num_shop as usize;
+        let players = vec![PlayerSecret::from("a"), PlayerSecret::from("b")];
+        let game = GameState::new_with_rng(players, setting, &mut rng).unwrap();
+        assert_eq!(game.deck.first(), Some(&Card::Disaster(finale.clone())));
+        let disasters = game
+            .deck
+            .iter()
+            .filter(|c| matches!(c, Card::Disaster(_)))
+            .count();
+        assert_eq!(disasters, game.setting.num_disasters as usize);
+
+        let mut near_end = game;
+        near_end.deck.truncate(num_shop + 1);
+        near_end.announce_catastrophe();
+        near_end.announce_catastrophe();
+        assert_eq!(
+            near_end.events,
+            vec![GameEvent::CatastropheAnnounced {
+                round: near_end.round,
+                disaster: finale.name,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_shield_mitigation() {
+        assert_eq!(mitigate(3, [1, 4, 2]), ([0, 2, 2], [1, 2, 0]));
+        assert_eq!(mitigate(0, [1, 1, 1]), ([1, 1, 1], [0; 3]));
+        let game = test_game(&["a"]);
+        let room = game.setting.rooms.iter().next().unwrap().clone();
+        let card = RoomCard {
+            abilities: vec![Ability::Shield(2)],
+            ..RoomCard::new("shield", room.clone())
+        };
+        let setting = game.setting.clone().with_cards(&[card]);
+        assert_eq!(setting.shield_of(&room.rotated_right()), 2);
+        assert_eq!(game.setting.shield_of(&room), 0);
+    }
+
+    #[test]
+    fn test_escalating_deck() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut setting = test_game(&["a"]).setting;
+        setting.disaster_order = DisasterOrder::Escalating;
+        let players = vec![PlayerSecret::from("a"), PlayerSecret::from("b")];
+        let game = GameState::new_with_rng(players, setting, &mut rng).unwrap();
+        let severities: Vec<u16> = game
+            .deck
+            .iter()
+            .rev()
+            .filter_map(|c| match c {
+                Card::Disaster(d) => Some(game.setting.severity(d)),
+                Card::Room(_) => None,
+            })
+            .collect();
+        assert_eq!(severities.len(), game.setting.num_disasters as usize);
+        assert!(severities.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_forecast_delays_disasters() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut game = test_game(&["a", "b"]);
+        game.setting.forecast_rounds = 2;
+        let disaster = game.setting.disasters.iter().next().unwrap().clone();
+        game.deck.retain(|c| matches!(c, Card::Room(_)));
+        game.deck.push(Card::Disaster(disaster.clone()));
+        let is_forecast = |g: &GameState| g.forecasts.iter().any(|f| f.disaster == disaster);
+        let has_struck = |g: &GameState| {
+            g.previous_disasters
+                .iter()
+                .chain(g.queued_disasters.iter())
+                .any(|d| d == &disaster)
+        };
+        let game = game.next_round_with_rng(&mut rng);
+        assert_eq!(game.forecasts[0].strike_round, game.round + 2);
+        assert!(is_forecast(&game) && !has_struck(&game));
+        let game = game.next_round_with_rng(&mut rng);
+        assert!(is_forecast(&game) && !has_struck(&game));
+        let game = game.next_round_with_rng(&mut rng);
+        assert!(!is_forecast(&game) && has_struck(&game));
+    }
+}
@@ -0,0 +1,164 @@
+use disastle_castle_rust::Action;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use std::result;
+
+use super::{GameSetting, GameState, PlayerSecret, Result, SettingError};
+
+/// A move of a recorded game, by the player at `seat`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum ReplayStep {
+    Action { seat: usize, action: Action },
+    Resign { seat: usize },
+    TimeOut { seat: usize },
+    VoteAbort { seat: usize },
+    Abort,
+}
+
+/// Everything needed to play a game again: the setting, the seed of its random number
+/// generator and every move. Players are identified by seat, so a replay can be shared
+/// without their secrets.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub setting: GameSetting,
+    /// Seats in the order the players were passed to `GameState::new_with_rng`.
+    pub players: Vec<usize>,
+    pub seed: u64,
+    pub steps: Vec<ReplayStep>,
+}
+
+impl Replay {
+    /// Every state of the game, from the deal to the last step. Players get stand-in
+    /// secrets that sort like their seats.
+    pub fn play(&self) -> Result<Vec<GameState>> {
+        let secret = |seat: usize| PlayerSecret::new(format!("seat-{:03}", seat));
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut game = GameState::new_with_rng(
+            self.players.iter().map(|&seat| secret(seat)).collect(),
+            self.setting.clone(),
+            &mut rng,
+        )?;
+        let mut states = vec![game.clone()];
+        for step in &self.steps {
+            game = match step {
+                ReplayStep::Action { seat, action } => {
+                    let seq = game.get_action_seq();
+                    game.action_with_rng(&secret(*seat), seq, action.clone(), &mut rng)?
+                }
+                ReplayStep::Resign { seat } => game.resign_with_rng(&secret(*seat), &mut rng)?,
+                ReplayStep::TimeOut { seat } => game.time_out_with_rng(&secret(*seat), &mut rng)?,
+                ReplayStep::VoteAbort { seat } => game.vote_abort(&secret(*seat))?,
+                ReplayStep::Abort => game.abort(),
+            };
+            states.push(game.clone());
+        }
+        Ok(states)
+    }
+}
+
+/// Game played from a seeded random number generator, recording each successful move into
+/// a `Replay`.
+#[derive(Clone, Debug)]
+pub struct Recorder {
+    game: GameState,
+    rng: StdRng,
+    replay: Replay,
+}
+
+impl Recorder {
+    pub fn new(
+        players: Vec<PlayerSecret>,
+        setting: GameSetting,
+        seed: u64,
+    ) -> result::Result<Recorder, Vec<SettingError>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let game = GameState::new_with_rng(players.clone(), setting.clone(), &mut rng)?;
+        let replay = Replay {
+            setting,
+            players: players.iter().map(|p| game.get_seat(p).unwrap()).collect(),
+            seed,
+            steps: Vec::new(),
+        };
+        Ok(Recorder { game, rng, replay })
+    }
+    pub fn get_game(&self) -> &GameState {
+        &self.game
+    }
+    pub fn get_replay(&self) -> &Replay {
+        &self.replay
+    }
+    pub fn action(
+        &mut self,
+        player_secret: &PlayerSecret,
+        expected_seq: u64,
+        action: Action,
+    ) -> Result<&GameState> {
+        let game = self.game.action_with_rng(
+            player_secret,
+            expected_seq,
+            action.clone(),
+            &mut self.rng,
+        )?;
+        let seat = self.seat(player_secret);
+        Ok(self.record(game, ReplayStep::Action { seat, action }))
+    }
+    pub fn resign(&mut self, player_secret: &PlayerSecret) -> Result<&GameState> {
+        let game = self.game.resign_with_rng(player_secret, &mut self.rng)?;
+        let seat = self.seat(player_secret);
+        Ok(self.record(game, ReplayStep::Resign { seat }))
+    }
+    pub fn time_out(&mut self, player_secret: &PlayerSecret) -> Result<&GameState> {
+        let game = self.game.time_out_with_rng(player_secret, &mut self.rng)?;
+        let seat = self.seat(player_secret);
+        Ok(self.record(game, ReplayStep::TimeOut { seat }))
+    }
+    pub fn vote_abort(&mut self, player_secret: &PlayerSecret) -> Result<&GameState> {
+        let game = self.game.vote_abort(player_secret)?;
+        let seat = self.seat(player_secret);
+        Ok(self.record(game, ReplayStep::VoteAbort { seat }))
+    }
+    pub fn abort(&mut self) -> &GameState {
+        let game = self.game.abort();
+        self.record(game, ReplayStep::Abort)
+    }
+    fn seat(&self, player_secret: &PlayerSecret) -> usize {
+        // Only called once the game accepted the move, so the player is seated
+        self.game.get_seat(player_secret).unwrap()
+    }
+    fn record(&mut self, game: GameState, step: ReplayStep) -> &GameState {
+        self.replay.steps.push(step);
+        self.game = game;
+        &self.game
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Recorder;
+    use crate::game::{test_game, PlayerSecret};
+
+    #[test]
+    fn test_replay_matches_recorded_game() {
+        let setting = test_game(&["a"]).setting;
+        let (a, b) = (PlayerSecret::from("a"), PlayerSecret::from("b"));
+        let mut recorder = Recorder::new(vec![b.clone(), a.clone()], setting, 42).unwrap();
+        for _ in 0..4 {
+            let game = recorder.get_game();
+            let player = if game.is_turn_player(&a) { &a } else { &b };
+            let action = game.possible_actions(player)[0].clone();
+            let seq = game.get_action_seq();
+            recorder.action(player, seq, action).unwrap();
+        }
+        recorder.time_out(&PlayerSecret::from("c")).unwrap_err();
+        recorder.resign(&a).unwrap();
+
+        let states = recorder.get_replay().play().unwrap();
+        assert_eq!(states.len(), 6);
+        let spectator = PlayerSecret::from("");
+        assert_eq!(
+            states.last().unwrap().player_view(&spectator),
+            recorder.get_game().player_view(&spectator)
+        );
+    }
+}
@@ -0,0 +1,169 @@
+//! Disaster rules shared by `GameState` and `SchrodingerGameState`, written over the parts of
+//! the table a disaster acts on so both states resolve disasters alike.
+
+use rand::Rng;
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::{mitigate, Forecast, GameSetting, PlayerSecret};
+use crate::{
+    castle::CastleExt,
+    disaster::{DamageContext, Disaster, Effect},
+};
+use disastle_castle_rust::{Castle, Room};
+
+/// Borrowed parts of a game that disasters read or change.
+pub(super) struct Table<'a> {
+    pub setting: &'a GameSetting,
+    pub round: u8,
+    /// Players whose castles disasters leave alone, such as those who resigned.
+    pub spared: &'a BTreeSet<PlayerSecret>,
+    pub castles: &'a mut BTreeMap<PlayerSecret, Castle>,
+    pub shop: &'a mut Vec<Room>,
+    pub discard: &'a mut Vec<Room>,
+    pub turn_order: &'a mut Vec<PlayerSecret>,
+    pub turn_index: &'a mut usize,
+    pub previous_disasters: &'a mut Vec<Disaster>,
+    pub power_disabled_round: &'a mut Option<u8>,
+}
+
+/// What resolving a disaster did beyond changing the table, for the caller to report.
+#[derive(Default)]
+pub(super) struct Resolution {
+    /// Damage absorbed by shields, by seat, for the castles that absorbed any.
+    pub absorbed: Vec<(usize, [u8; 3])>,
+    /// Players whose castles were lost, in turn order.
+    pub lost: Vec<PlayerSecret>,
+}
+
+impl Table<'_> {
+    /// Resolve `disaster`: its damage, its targeted strike, then its effects. Lost castles
+    /// leave the turn order after the strike and again after the effects.
+    pub fn resolve_disaster<R: Rng + ?Sized>(
+        &mut self,
+        disaster: Disaster,
+        rng: &mut R,
+    ) -> Resolution {
+        let mut resolution = Resolution::default();
+        let previous_disasters = self.previous_disasters.len() as u8;
+        let players = self.castles.len() as u8;
+        let shielded = *self.power_disabled_round != Some(self.round);
+        for (seat, (secret, castle)) in self.castles.iter_mut().enumerate() {
+            if self.spared.contains(secret) {
+                continue;
+            }
+            let shield = if shielded {
+                self.setting.castle_shield(castle)
+            } else {
+                0
+            };
+            let context = DamageContext {
+                previous_disasters,
+                rooms: castle.rooms.len() as u8,
+                round: self.round,
+                players,
+            };
+            let (dealt, absorbed) = mitigate(shield, disaster.damage(&context));
+            log_debug!(seat, ?dealt, ?absorbed, "castle damaged");
+            *castle = castle.deal_damage(dealt[0], dealt[1], dealt[2]);
+            if absorbed != [0; 3] {
+                resolution.absorbed.push((seat, absorbed));
+            }
+        }
+        if let Some(target) = &disaster.target {
+            for castle in struck(self.castles, self.spared) {
+                if let Ok((rest, mut destroyed)) = castle.strike(target, rng) {
+                    *castle = rest;
+                    self.discard.append(&mut destroyed);
+                }
+            }
+        }
+        resolution.lost.extend(self.sweep_lost_castles());
+        for &effect in disaster.effects.iter() {
+            self.resolve_effect(effect);
+        }
+        resolution.lost.extend(self.sweep_lost_castles());
+        self.previous_disasters.push(disaster);
+        resolution
+    }
+    /// Take lost castles out of the turn order, returning their players.
+    pub fn sweep_lost_castles(&mut self) -> Vec<PlayerSecret> {
+        let mut turn_order = Vec::new();
+        let mut lost = Vec::new();
+        for (index, secret) in self.turn_order.iter().enumerate() {
+            if !self.castles[secret].is_lost() {
+                turn_order.push(secret.clone());
+            } else {
+                lost.push(secret.clone());
+                if index < *self.turn_index {
+                    *self.turn_index -= 1;
+                }
+            }
+        }
+        if *self.turn_index >= turn_order.len() {
+            *self.turn_index = 0;
+        }
+        *self.turn_order = turn_order;
+        lost
+    }
+    fn resolve_effect(&mut self, effect: Effect) {
+        match effect {
+            Effect::DiscardShop => self.discard.append(self.shop),
+            Effect::SkipNextTurn => {
+                // Wraps to the start of the turn order without starting a new round
+                if self.turn_order.len() > 1 {
+                    *self.turn_index = (*self.turn_index + 1) % self.turn_order.len();
+                }
+            }
+            Effect::RemoveOuterRoom => {
+                for castle in struck(self.castles, self.spared) {
+                    let pos = match castle.outer_room() {
+                        Some(pos) => pos,
+                        None => continue,
+                    };
+                    let room = castle.room_at(pos).unwrap().clone();
+                    if let Ok(rest) = castle.without_room(pos) {
+                        *castle = rest;
+                        self.discard.push(room);
+                    }
+                }
+            }
+            Effect::DisablePower => *self.power_disabled_round = Some(self.round),
+        }
+    }
+}
+
+/// Castles still standing that are not spared.
+fn struck<'a>(
+    castles: &'a mut BTreeMap<PlayerSecret, Castle>,
+    spared: &'a BTreeSet<PlayerSecret>,
+) -> impl Iterator<Item = &'a mut Castle> {
+    castles
+        .iter_mut()
+        .filter(move |(secret, castle)| !castle.is_lost() && !spared.contains(secret))
+        .map(|(_, castle)| castle)
+}
+
+/// Order freshly drawn `disasters` for resolving from the back, so a catastrophe resolves
+/// last. With forecasts, they are revealed instead and the forecasts due by `round` are
+/// returned in their place.
+pub(super) fn due_disasters(
+    mut disasters: Vec<Disaster>,
+    forecasts: &mut Vec<Forecast>,
+    forecast_rounds: u8,
+    round: u8,
+) -> Vec<Disaster> {
+    disasters.sort_by_key(|d| !d.catastrophe);
+    if forecast_rounds == 0 {
+        return disasters;
+    }
+    // Drawn disasters are revealed now and resolve in the usual order once due
+    let strike_round = round.saturating_add(forecast_rounds);
+    forecasts.extend(disasters.into_iter().rev().map(|disaster| Forecast {
+        disaster,
+        strike_round,
+    }));
+    let (due, pending): (Vec<Forecast>, Vec<Forecast>) =
+        forecasts.drain(..).partition(|f| f.strike_round <= round);
+    *forecasts = pending;
+    due.into_iter().rev().map(|f| f.disaster).collect()
+}
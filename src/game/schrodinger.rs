@@ -1,4 +1,6 @@
-use rand::{seq::IteratorRandom, thread_rng, Rng};
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{seq::IteratorRandom, Rng};
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
@@ -8,8 +10,8 @@ use std::{
 };
 
 use super::error::GameError;
-use super::GameSetting;
-pub use crate::disaster::Disaster;
+use super::{rules, DisasterOrder, Forecast, GameSetting, PlayerSecret, RoomCopies};
+use crate::disaster::Disaster;
 use disastle_castle_rust::{Action, Castle, Room};
 
 type Result<T> = result::Result<T, GameError>;
@@ -20,17 +22,22 @@ pub struct SchrodingerGameState {
     pub discard: Vec<Room>,
     pub previous_disasters: Vec<Disaster>,
     pub queued_disasters: Vec<Disaster>,
+    #[serde(default)]
+    pub forecasts: Vec<Forecast>,
     pub round: u8,
     pub setting: GameSetting,
-    pub castles: BTreeMap<String, Castle>,
-    pub turn_order: Vec<String>,
+    pub castles: BTreeMap<PlayerSecret, Castle>,
+    pub turn_order: Vec<PlayerSecret>,
     pub turn_index: usize,
-    pub possible_rooms: BTreeSet<Room>,
+    /// Copies of each room still in the deck. Rooms with none left are not listed.
+    pub possible_rooms: Vec<RoomCopies>,
     pub possible_disasters: BTreeSet<Disaster>,
+    #[serde(default)]
+    pub power_disabled_round: Option<u8>,
 }
 
 impl SchrodingerGameState {
-    pub fn all_players_possible_actions(&self) -> Vec<(String, Action)> {
+    pub fn all_players_possible_actions(&self) -> Vec<(PlayerSecret, Action)> {
         self.turn_order
             .iter()
             .map(|secret| {
@@ -41,7 +48,7 @@ impl SchrodingerGameState {
             .flatten()
             .collect()
     }
-    pub fn possible_actions(&self, player_secret: &str) -> Vec<Action> {
+    pub fn possible_actions(&self, player_secret: &PlayerSecret) -> Vec<Action> {
         if let Some(castle) = self.castles.get(player_secret) {
             if self.is_turn_player(player_secret) {
                 return castle.possible_actions(&self.shop);
@@ -49,7 +56,20 @@ impl SchrodingerGameState {
         }
         return Vec::new();
     }
-    pub fn action(&self, player_secret: &str, action: Action) -> Result<SchrodingerGameState> {
+    #[cfg(feature = "std")]
+    pub fn action(
+        &self,
+        player_secret: &PlayerSecret,
+        action: Action,
+    ) -> Result<SchrodingerGameState> {
+        self.action_with_rng(player_secret, action, &mut thread_rng())
+    }
+    pub fn action_with_rng<R: Rng + ?Sized>(
+        &self,
+        player_secret: &PlayerSecret,
+        action: Action,
+        rng: &mut R,
+    ) -> Result<SchrodingerGameState> {
         if !self.castles.contains_key(player_secret) {
             return Err(GameError::InvalidPlayer);
         } else if !self.is_turn_player(player_secret) {
@@ -58,65 +78,83 @@ impl SchrodingerGameState {
         match action {
             Action::Place(index, pos) => {
                 if index >= self.shop.len() {
-                    return Err(GameError::InvalidShopIndex);
+                    return Err(GameError::InvalidShopIndex {
+                        index,
+                        shop_len: self.shop.len(),
+                    });
                 }
                 let mut game = self.clone();
                 let room = game.shop.remove(index);
                 game.castles.insert(
-                    player_secret.to_string(),
-                    game.castles[player_secret].place_room(room, pos)?,
+                    player_secret.clone(),
+                    game.castles[player_secret]
+                        .place_room(room, pos)
+                        .map_err(|e| GameError::castle(&action, e))?,
                 );
-                game = game.next_turn();
+                game = game.next_turn_with_rng(rng);
                 Ok(game)
             }
             Action::Move(from, to) => {
                 let mut game = self.clone();
                 game.castles.insert(
-                    player_secret.to_string(),
-                    game.castles[player_secret].move_room(from, to)?,
+                    player_secret.clone(),
+                    game.castles[player_secret]
+                        .move_room(from, to)
+                        .map_err(|e| GameError::castle(&action, e))?,
                 );
-                game = game.next_turn();
+                game = game.next_turn_with_rng(rng);
                 Ok(game)
             }
             Action::Swap(pos1, pos2) => {
                 let mut game = self.clone();
                 game.castles.insert(
-                    player_secret.to_string(),
+                    player_secret.clone(),
                     game.castles
                         .get(player_secret)
                         .unwrap()
-                        .swap_room(pos1, pos2)?,
+                        .swap_room(pos1, pos2)
+                        .map_err(|e| GameError::castle(&action, e))?,
                 );
-                game = game.next_turn();
+                game = game.next_turn_with_rng(rng);
                 Ok(game)
             }
             Action::Discard(pos) => {
                 let mut game = self.clone();
-                let (castle, room) = game.castles[player_secret].discard_room(pos)?;
+                let (castle, room) = game.castles[player_secret]
+                    .discard_room(pos)
+                    .map_err(|e| GameError::castle(&action, e))?;
                 game.discard.push(room);
-                game.castles.insert(player_secret.to_string(), castle);
+                game.castles.insert(player_secret.clone(), castle);
                 game.sweep_lost_castles();
                 if game.castles.values().all(|c| c.damage == 0 || c.is_lost())
                     && game.queued_disasters.len() > 0
                 {
                     let disaster = game.queued_disasters.pop().unwrap();
-                    game = game.resolve_disaster(disaster);
+                    game = game.resolve_disaster(disaster, rng);
                 }
                 Ok(game)
             }
         }
     }
+    #[cfg(feature = "std")]
     pub fn next_turn(&self) -> SchrodingerGameState {
+        self.next_turn_with_rng(&mut thread_rng())
+    }
+    pub fn next_turn_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> SchrodingerGameState {
         let mut game = self.clone();
         game.turn_index += 1;
         if game.turn_index >= game.turn_order.len() {
             game.turn_index = 0;
             game.turn_order.rotate_left(1);
-            game = game.next_round()
+            game = game.next_round_with_rng(rng)
         }
         game
     }
+    #[cfg(feature = "std")]
     pub fn next_round(&self) -> SchrodingerGameState {
+        self.next_round_with_rng(&mut thread_rng())
+    }
+    pub fn next_round_with_rng<R: Rng + ?Sized>(&self, rng: &mut R) -> SchrodingerGameState {
         let mut game = self.clone();
         game.round += 1;
         game.discard.append(&mut game.shop);
@@ -126,39 +164,53 @@ impl SchrodingerGameState {
             && game.setting.num_disasters as usize
                 - game.previous_disasters.len()
                 - game.queued_disasters.len()
+                - game.forecasts.len()
                 - disasters.len()
                 > 0
         {
             let num_disasters_left = if (game.setting.num_safe as usize)
-                > self.setting.rooms.len() - self.possible_rooms.len()
+                > game.setting.num_rooms() - game.rooms_left()
             {
                 0 // Still safe rooms left
             } else {
                 game.setting.num_disasters as usize
                     - game.previous_disasters.len()
                     - game.queued_disasters.len()
+                    - game.forecasts.len()
                     - disasters.len()
             };
-            if thread_rng().gen_ratio(
-                num_disasters_left as u32,
-                (game.possible_rooms.len() + num_disasters_left) as u32,
-            ) {
+            let cards_left = game.rooms_left() + num_disasters_left;
+            if cards_left == 0 {
+                break;
+            }
+            if rng.gen_ratio(num_disasters_left as u32, cards_left as u32) {
+                // Catastrophes are only possible as the last disaster, and then mandatory
+                let is_last = num_disasters_left == 1;
+                // Escalating decks never deal a disaster milder than the previous one
+                let floor = match game.setting.disaster_order {
+                    DisasterOrder::Escalating => game
+                        .previous_disasters
+                        .iter()
+                        .chain(game.queued_disasters.iter())
+                        .chain(game.forecasts.iter().map(|f| &f.disaster))
+                        .chain(disasters.iter())
+                        .map(|d| game.setting.severity(d))
+                        .max(),
+                    DisasterOrder::Shuffled => None,
+                };
                 let disaster = game
                     .possible_disasters
                     .iter()
-                    .choose(&mut thread_rng())
+                    .filter(|d| d.catastrophe == is_last)
+                    .filter(|d| !matches!(floor, Some(floor) if game.setting.severity(d) < floor))
+                    .choose(rng)
+                    .or_else(|| game.possible_disasters.iter().choose(rng))
                     .unwrap()
                     .clone();
                 game.possible_disasters.remove(&disaster);
                 disasters.push(disaster);
             } else {
-                let room = game
-                    .possible_rooms
-                    .iter()
-                    .choose(&mut thread_rng())
-                    .unwrap()
-                    .clone();
-                game.possible_rooms.remove(&room);
+                let room = game.draw_room(rng);
                 game.shop.push(room);
             }
             if !redealt && disasters.len() > 1 {
@@ -169,41 +221,69 @@ impl SchrodingerGameState {
                 redealt = true;
             }
         }
-        if disasters.len() == 0 {
-            return game;
+        let mut disasters = rules::due_disasters(
+            disasters,
+            &mut game.forecasts,
+            game.setting.forecast_rounds,
+            game.round,
+        );
+        if let Some(disaster) = disasters.pop() {
+            game = game.resolve_disaster(disaster, rng);
+            game.queued_disasters = disasters;
         }
-        let disaster = disasters.pop().unwrap();
-        game = game.resolve_disaster(disaster);
-        game.queued_disasters = disasters;
         game
     }
-    fn sweep_lost_castles(&mut self) {
-        let mut turn_order = Vec::new();
-        for (index, secret) in self.turn_order.iter().enumerate() {
-            let castle = &self.castles[secret];
-            if !castle.is_lost() {
-                turn_order.push(secret.to_string());
-            } else {
-                if index < self.turn_index {
-                    self.turn_index -= 1;
+    fn rooms_left(&self) -> usize {
+        self.possible_rooms.iter().map(|r| r.copies as usize).sum()
+    }
+    /// Take one of the copies left in the deck, weighted by their number. Only called while
+    /// some are left.
+    fn draw_room<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Room {
+        let mut pick = rng.gen_range(0..self.rooms_left());
+        let index = self
+            .possible_rooms
+            .iter()
+            .position(|r| match pick.checked_sub(r.copies as usize) {
+                Some(rest) => {
+                    pick = rest;
+                    false
                 }
-            }
+                None => true,
+            })
+            .unwrap();
+        let entry = &mut self.possible_rooms[index];
+        entry.copies -= 1;
+        let room = entry.room.clone();
+        if entry.copies == 0 {
+            self.possible_rooms.remove(index);
         }
-        if self.turn_index >= turn_order.len() {
-            self.turn_index = 0;
+        room
+    }
+    /// The parts of the game disasters act on. Every player is still in the game here.
+    fn table<'a>(&'a mut self, spared: &'a BTreeSet<PlayerSecret>) -> rules::Table<'a> {
+        rules::Table {
+            setting: &self.setting,
+            round: self.round,
+            spared,
+            castles: &mut self.castles,
+            shop: &mut self.shop,
+            discard: &mut self.discard,
+            turn_order: &mut self.turn_order,
+            turn_index: &mut self.turn_index,
+            previous_disasters: &mut self.previous_disasters,
+            power_disabled_round: &mut self.power_disabled_round,
         }
-        self.turn_order = turn_order;
     }
-    fn resolve_disaster(&self, disaster: Disaster) -> SchrodingerGameState {
+    fn sweep_lost_castles(&mut self) {
+        self.table(&BTreeSet::new()).sweep_lost_castles();
+    }
+    fn resolve_disaster<R: Rng + ?Sized>(
+        &self,
+        disaster: Disaster,
+        rng: &mut R,
+    ) -> SchrodingerGameState {
         let mut game = self.clone();
-        let diamond = disaster.diamond_damage(game.previous_disasters.len() as u8);
-        let cross = disaster.cross_damage(game.previous_disasters.len() as u8);
-        let moon = disaster.moon_damage(game.previous_disasters.len() as u8);
-        for castle in game.castles.values_mut() {
-            *castle = castle.deal_damage(diamond, cross, moon);
-        }
-        game.sweep_lost_castles();
-        game.previous_disasters.push(disaster);
+        game.table(&BTreeSet::new()).resolve_disaster(disaster, rng);
         game
     }
 }
@@ -242,25 +322,25 @@ impl SchrodingerGameState {
         self.turn_order.len() <= 1
             || self.previous_disasters.len() == self.setting.num_disasters as usize
     }
-    pub fn is_victorious(&self, secret: &str) -> bool {
-        let mut castles: Vec<(&String, &Castle)> = self.castles.iter().collect();
+    pub fn is_victorious(&self, secret: &PlayerSecret) -> bool {
+        let mut castles: Vec<(&PlayerSecret, &Castle)> = self.castles.iter().collect();
         castles.sort_unstable_by(|(_, a), (_, b)| compare_game_state(b, a)); // Reversed for descending order
         let winner = castles.first();
         if winner.is_none() {
             return false;
         }
         let winner = winner.unwrap().clone();
-        let winners: Vec<(&String, &Castle)> = castles
+        let winners: Vec<(&PlayerSecret, &Castle)> = castles
             .into_iter()
             .clone()
             .filter(|(_, castle)| matches!(compare_game_state(castle, winner.1), Ordering::Equal))
             .collect();
         winners.iter().any(|(s, _)| s == &secret)
     }
-    pub fn is_player(&self, secret: &str) -> bool {
+    pub fn is_player(&self, secret: &PlayerSecret) -> bool {
         self.castles.contains_key(secret)
     }
-    pub fn is_turn_player(&self, secret: &str) -> bool {
+    pub fn is_turn_player(&self, secret: &PlayerSecret) -> bool {
         // Check if player need discard
         if let Some(castle) = self.castles.get(secret) {
             if castle.damage > 0 && !castle.is_lost() {
@@ -272,12 +352,12 @@ impl SchrodingerGameState {
             return false;
         }
         // Check if it is player's turn
-        self.turn_order.len() > 0 && self.turn_order[self.turn_index] == secret
+        self.turn_order.len() > 0 && self.turn_order[self.turn_index] == *secret
     }
     pub fn get_turn_index(&self) -> usize {
         self.turn_index
     }
-    pub fn get_player_turn_index(&self, secret: &str) -> Result<usize> {
+    pub fn get_player_turn_index(&self, secret: &PlayerSecret) -> Result<usize> {
         if !self.castles.contains_key(secret) {
             return Err(GameError::InvalidPlayer);
         }
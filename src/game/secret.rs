@@ -0,0 +1,87 @@
+#[cfg(feature = "std")]
+use rand::thread_rng;
+use rand::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Opaque token identifying a player to the game.
+///
+/// `Debug` never prints the value, so a secret can be logged alongside game state without
+/// leaking it. Comparisons are not constant time: game state looks players up in maps
+/// ordered by `Ord`, which stops at the first differing byte, so servers should not let
+/// clients time guesses against a game.
+#[derive(Clone, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PlayerSecret(String);
+
+impl PlayerSecret {
+    pub fn new(secret: String) -> PlayerSecret {
+        PlayerSecret(secret)
+    }
+    /// Random version 4 UUID, 122 bits that cannot be guessed as long as `rng` is
+    /// cryptographically secure.
+    pub fn generate<R: RngCore + CryptoRng + ?Sized>(rng: &mut R) -> PlayerSecret {
+        let mut bytes = [0u8; 16];
+        rng.fill_bytes(&mut bytes);
+        bytes[6] = (bytes[6] & 0x0f) | 0x40;
+        bytes[8] = (bytes[8] & 0x3f) | 0x80;
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        PlayerSecret(format!(
+            "{}-{}-{}-{}-{}",
+            &hex[..8],
+            &hex[8..12],
+            &hex[12..16],
+            &hex[16..20],
+            &hex[20..]
+        ))
+    }
+    #[cfg(feature = "std")]
+    pub fn random() -> PlayerSecret {
+        PlayerSecret::generate(&mut thread_rng())
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl fmt::Debug for PlayerSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PlayerSecret(<redacted>)")
+    }
+}
+
+impl From<String> for PlayerSecret {
+    fn from(secret: String) -> Self {
+        PlayerSecret(secret)
+    }
+}
+
+impl From<&str> for PlayerSecret {
+    fn from(secret: &str) -> Self {
+        PlayerSecret(secret.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlayerSecret;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_secret_comparison_and_redaction() {
+        let secret = PlayerSecret::from("hunter2");
+        assert_eq!(secret, PlayerSecret::from("hunter2".to_string()));
+        assert_ne!(secret, PlayerSecret::from("hunter3"));
+        assert_ne!(secret, PlayerSecret::from("hunter"));
+        assert!(!format!("{:?}", secret).contains("hunter2"));
+
+        let mut rng = StdRng::seed_from_u64(0);
+        let random = PlayerSecret::generate(&mut rng);
+        assert_eq!(random.as_str().len(), 36);
+        assert_eq!(&random.as_str()[14..15], "4");
+        assert_ne!(random, PlayerSecret::generate(&mut rng));
+    }
+}
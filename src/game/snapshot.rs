@@ -0,0 +1,89 @@
+use std::{
+    fs::File,
+    io::{self, Read, Write},
+    path::Path,
+    result,
+};
+
+use super::{migrate, GameState, Versioned, SCHEMA_VERSION};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SnapshotFormat {
+    Ron,
+    Json,
+}
+
+impl SnapshotFormat {
+    /// `.json` files are JSON, anything else is RON like the card data files.
+    pub fn from_path(path: &Path) -> SnapshotFormat {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => SnapshotFormat::Json,
+            _ => SnapshotFormat::Ron,
+        }
+    }
+}
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+impl GameState {
+    pub fn save_to(&self, path: &Path) -> result::Result<(), io::Error> {
+        let content = match SnapshotFormat::from_path(path) {
+            SnapshotFormat::Ron => {
+                ron::ser::to_string_pretty(&self.to_versioned(), ron::ser::PrettyConfig::new())
+                    .map_err(invalid_data)?
+            }
+            SnapshotFormat::Json => {
+                serde_json::to_string_pretty(&self.to_versioned()).map_err(invalid_data)?
+            }
+        };
+        let mut file = File::create(path)?;
+        file.write_all(content.as_bytes())
+    }
+    pub fn load_from(path: &Path) -> result::Result<GameState, io::Error> {
+        let mut file = File::open(path)?;
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        match SnapshotFormat::from_path(path) {
+            SnapshotFormat::Ron => match ron::from_str::<Versioned<GameState>>(&content) {
+                Ok(versioned) if versioned.version > SCHEMA_VERSION => Err(invalid_data(format!(
+                    "Snapshot version {} is newer than the supported version {}",
+                    versioned.version, SCHEMA_VERSION
+                ))),
+                Ok(versioned) => versioned.state.rebuild_castles().map_err(invalid_data),
+                // Snapshots written before versioning hold a bare GameState
+                Err(e) => ron::from_str::<GameState>(&content)
+                    .map_err(|_| invalid_data(e))?
+                    .rebuild_castles()
+                    .map_err(invalid_data),
+            },
+            SnapshotFormat::Json => {
+                let value = serde_json::from_str(&content).map_err(invalid_data)?;
+                migrate(value).map_err(invalid_data)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game::{test_game, GameState};
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_save_load_snapshot() {
+        let game = test_game(&["a", "b", "c"]);
+        for extension in ["ron", "json"].iter() {
+            let name = format!("disastle_snapshot_{}.{}", process::id(), extension);
+            let path = env::temp_dir().join(name);
+            game.save_to(&path).unwrap();
+            let loaded = GameState::load_from(&path);
+            fs::remove_file(&path).unwrap();
+            assert_eq!(loaded.unwrap(), game);
+        }
+    }
+}
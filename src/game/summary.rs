@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+use super::GameState;
+
+/// Outcome of a game worth keeping once it is over. Players are identified by seat, as in
+/// `PlayerView`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct GameSummary {
+    pub rounds: u8,
+    /// Seats grouped by place, best first, as in `GameState::get_standings`.
+    pub standings: Vec<Vec<usize>>,
+    /// Seats of the winners, none for an aborted game.
+    pub winners: Vec<usize>,
+    /// Treasure of each castle, by seat.
+    pub treasure: Vec<u8>,
+    /// Names of the rooms other than thrones in each castle, by seat.
+    pub rooms: Vec<Vec<String>>,
+    /// Names of the disasters that struck, in order.
+    pub disasters: Vec<String>,
+    pub resigned: Vec<usize>,
+    pub aborted: bool,
+}
+
+impl GameState {
+    pub fn summary(&self) -> GameSummary {
+        GameSummary {
+            rounds: self.round,
+            standings: self.get_standings(),
+            winners: self
+                .castles
+                .keys()
+                .enumerate()
+                .filter(|(_, s)| self.is_victorious(s))
+                .map(|(seat, _)| seat)
+                .collect(),
+            treasure: self.castles.values().map(|c| c.get_treasure()).collect(),
+            rooms: self
+                .castles
+                .values()
+                .map(|c| {
+                    c.rooms
+                        .values()
+                        .filter(|r| !r.throne)
+                        .map(|r| r.name.clone())
+                        .collect()
+                })
+                .collect(),
+            disasters: self
+                .previous_disasters
+                .iter()
+                .map(|d| d.name.clone())
+                .collect(),
+            resigned: self
+                .resigned
+                .iter()
+                .filter_map(|s| self.get_seat(s))
+                .collect(),
+            aborted: self.aborted,
+        }
+    }
+}
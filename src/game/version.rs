@@ -0,0 +1,190 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+use super::{GameState, SchrodingerGameState};
+use crate::{
+    castle::{BuildError, CastleExt},
+    error::{ErrorBody, ErrorCode},
+};
+
+/// Version of the serialized game state layout.
+///
+/// 1. Unversioned states written before the envelope existed.
+/// 2. `GameState::action_seq` added.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Envelope recording which schema version a stored state was written with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub state: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(state: T) -> Versioned<T> {
+        Versioned {
+            version: SCHEMA_VERSION,
+            state,
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum MigrationError {
+    #[error("Schema version {version} is newer than the supported version {supported}")]
+    UnsupportedVersion { version: u32, supported: u32 },
+    #[error("Schema version {0} is not a version number")]
+    InvalidVersion(Value),
+    #[error("Malformed game state: {0}")]
+    Malformed(#[from] serde_json::Error),
+    #[error("Invalid castle in game state: {0}")]
+    InvalidCastle(#[from] BuildError),
+}
+
+impl MigrationError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            MigrationError::UnsupportedVersion { .. } => ErrorCode::UnsupportedVersion,
+            MigrationError::InvalidVersion(_)
+            | MigrationError::Malformed(_)
+            | MigrationError::InvalidCastle(_) => ErrorCode::MalformedState,
+        }
+    }
+    pub fn to_body(&self) -> ErrorBody {
+        ErrorBody::new(self.code(), self.to_string())
+    }
+}
+
+type Migration = fn(&mut Value);
+
+/// Split `value` into its schema version and the bare state, then run every migration
+/// from that version up to `SCHEMA_VERSION`. `migrations[i]` upgrades version `i + 1`.
+fn upgrade(value: Value, migrations: &[Migration]) -> Result<Value, MigrationError> {
+    let (version, mut state) = match value {
+        Value::Object(mut map) if map.contains_key("version") && map.contains_key("state") => {
+            let version = match map["version"].as_u64().map(u32::try_from) {
+                Some(Ok(version)) if version > 0 => version,
+                _ => {
+                    return Err(MigrationError::InvalidVersion(
+                        map.remove("version").unwrap(),
+                    ))
+                }
+            };
+            (version, map.remove("state").unwrap())
+        }
+        state => (1, state),
+    };
+    if version > SCHEMA_VERSION {
+        return Err(MigrationError::UnsupportedVersion {
+            version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+    for migration in migrations.iter().skip((version as usize).saturating_sub(1)) {
+        migration(&mut state);
+    }
+    Ok(state)
+}
+
+fn add_action_seq(state: &mut Value) {
+    if let Some(map) = state.as_object_mut() {
+        map.entry("action_seq".to_string())
+            .or_insert(Value::from(0));
+    }
+}
+
+/// Load a game state stored with any schema version, versioned or not.
+pub fn migrate(value: Value) -> Result<GameState, MigrationError> {
+    let state = upgrade(value, &[add_action_seq])?;
+    let game: GameState = serde_json::from_value(state)?;
+    Ok(game.rebuild_castles()?)
+}
+
+pub fn migrate_schrodinger(value: Value) -> Result<SchrodingerGameState, MigrationError> {
+    let state = upgrade(value, &[|_| {}])?;
+    Ok(serde_json::from_value(state)?)
+}
+
+impl GameState {
+    /// Regenerate the connection graph of every castle failing validation from its rooms,
+    /// so a hand-edited or corrupted save can't leave the graph and the room map
+    /// disagreeing. Lost castles may have no throne to rebuild from and are kept as stored.
+    pub(super) fn rebuild_castles(mut self) -> Result<GameState, BuildError> {
+        for castle in self.castles.values_mut() {
+            if castle.is_lost() || castle.validate().is_ok() {
+                continue;
+            }
+            *castle = castle.rebuild_connections()?;
+        }
+        Ok(self)
+    }
+    pub fn to_versioned(&self) -> Versioned<&GameState> {
+        Versioned::new(self)
+    }
+}
+
+impl SchrodingerGameState {
+    pub fn to_versioned(&self) -> Versioned<&SchrodingerGameState> {
+        Versioned::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{migrate, MigrationError, SCHEMA_VERSION};
+    use crate::castle::{test_castles, CastleExt};
+    use crate::game::test_game;
+
+    #[test]
+    fn test_migrate_versions() {
+        let game = test_game(&["a", "b"]);
+        let current = serde_json::to_value(game.to_versioned()).unwrap();
+        assert_eq!(migrate(current).unwrap(), game);
+
+        let mut unversioned = serde_json::to_value(&game).unwrap();
+        unversioned.as_object_mut().unwrap().remove("action_seq");
+        assert_eq!(migrate(unversioned).unwrap(), game);
+
+        let mut future = serde_json::to_value(game.to_versioned()).unwrap();
+        future["version"] = (SCHEMA_VERSION + 1).into();
+        assert!(matches!(
+            migrate(future),
+            Err(MigrationError::UnsupportedVersion { .. })
+        ));
+        for version in [
+            serde_json::json!("2"),
+            (u32::MAX as u64 + 2).into(),
+            0.into(),
+        ] {
+            let mut invalid = serde_json::to_value(game.to_versioned()).unwrap();
+            invalid["version"] = version;
+            assert!(matches!(
+                migrate(invalid),
+                Err(MigrationError::InvalidVersion(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_migrate_keeps_castles() {
+        let mut game = test_game(&["a", "b"]);
+        let secrets: Vec<_> = game.castles.keys().cloned().collect();
+        // A castle away from where a rebuild would put it, and one that lost its throne
+        let castle = test_castles().remove(0);
+        let mut moved = castle.clone();
+        moved.rooms = castle
+            .iter()
+            .map(|(pos, room)| (pos.offset(3, -2).into(), room.clone()))
+            .collect();
+        assert_eq!(moved.rebuild_connections().unwrap().rooms, moved.rooms);
+        game.castles.insert(secrets[0].clone(), moved);
+        let lost = game.castles.get_mut(&secrets[1]).unwrap();
+        lost.rooms.retain(|_, room| !room.throne);
+        lost.damage = 1;
+        assert!(game.castles[&secrets[1]].is_lost());
+        let stored = serde_json::to_value(game.to_versioned()).unwrap();
+        assert_eq!(migrate(stored).unwrap(), game);
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+use super::{Forecast, GameEvent, GameState, PlayerSecret};
+use crate::disaster::Disaster;
+use disastle_castle_rust::{Action, Castle, Room};
+
+/// The part of a game one player may see: everything but the deck and the other players'
+/// secrets. Players are identified by seat, their index in `castles`, which stays stable for
+/// the whole game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PlayerView {
+    pub seat: Option<usize>,
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<serde_json::Value>"))]
+    pub castles: Vec<Castle>,
+    pub turn_order: Vec<usize>,
+    pub to_act: Vec<usize>,
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<crate::schema::RoomSchema>"))]
+    pub shop: Vec<Room>,
+    #[cfg_attr(feature = "schema", schemars(with = "Vec<crate::schema::RoomSchema>"))]
+    pub discard: Vec<Room>,
+    pub previous_disasters: Vec<Disaster>,
+    pub queued_disasters: Vec<Disaster>,
+    #[serde(default)]
+    pub forecasts: Vec<Forecast>,
+    pub deck_size: usize,
+    pub round: u8,
+    #[serde(default)]
+    pub events: Vec<GameEvent>,
+    pub action_seq: u64,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "Vec<crate::schema::ActionSchema>")
+    )]
+    pub possible_actions: Vec<Action>,
+    pub is_over: bool,
+    #[serde(default)]
+    pub power_disabled: bool,
+    /// Seats of the players who resigned.
+    #[serde(default)]
+    pub resigned: Vec<usize>,
+    /// Seats of the players who voted to abort the game.
+    #[serde(default)]
+    pub abort_votes: Vec<usize>,
+}
+
+#[cfg(feature = "render-svg")]
+impl PlayerView {
+    /// SVG image of every castle and the shop.
+    pub fn render_svg(&self) -> String {
+        crate::castle::svg::table(&self.castles, &self.shop)
+    }
+}
+
+impl GameState {
+    /// SVG image of every castle and the shop, as any player or spectator sees them.
+    #[cfg(feature = "render-svg")]
+    pub fn render_svg(&self) -> String {
+        let castles: Vec<Castle> = self.castles.values().cloned().collect();
+        crate::castle::svg::table(&castles, &self.shop)
+    }
+    pub fn get_seat(&self, secret: &PlayerSecret) -> Option<usize> {
+        self.castles.keys().position(|s| s == secret)
+    }
+    /// View of the game for `secret`. Secrets that are not in the game get the view of a
+    /// spectator, with no seat and no possible actions.
+    pub fn player_view(&self, secret: &PlayerSecret) -> PlayerView {
+        let seats: Vec<&PlayerSecret> = self.castles.keys().collect();
+        let seat_of = |secret: &PlayerSecret| seats.iter().position(|&s| s == secret).unwrap();
+        PlayerView {
+            seat: self.get_seat(secret),
+            castles: self.castles.values().cloned().collect(),
+            turn_order: self.turn_order.iter().map(seat_of).collect(),
+            to_act: seats
+                .iter()
+                .enumerate()
+                .filter(|(_, s)| self.is_turn_player(s))
+                .map(|(seat, _)| seat)
+                .collect(),
+            shop: self.shop.clone(),
+            discard: self.discard.clone(),
+            previous_disasters: self.previous_disasters.clone(),
+            queued_disasters: self.queued_disasters.clone(),
+            forecasts: self.forecasts.clone(),
+            deck_size: self.deck.len(),
+            round: self.round,
+            events: self.events.clone(),
+            action_seq: self.action_seq,
+            possible_actions: self.possible_actions(secret),
+            is_over: self.is_over(),
+            power_disabled: self.is_power_disabled(),
+            resigned: self.resigned.iter().map(seat_of).collect(),
+            abort_votes: self.abort_votes.iter().map(seat_of).collect(),
+        }
+    }
+}
+
+/// Spectator views held back by a number of moves, so that players watching a stream of the
+/// game learn nothing their opponents have not seen for a while.
+#[derive(Clone, Debug)]
+pub struct DelayedViews {
+    delay: u64,
+    views: VecDeque<PlayerView>,
+}
+
+impl DelayedViews {
+    pub fn new(delay: u64) -> DelayedViews {
+        DelayedViews {
+            delay,
+            views: VecDeque::new(),
+        }
+    }
+    /// Queue the spectator view of `game`, to be called after every move.
+    pub fn push(&mut self, game: &GameState) {
+        self.views
+            .push_back(game.player_view(&PlayerSecret::from("")));
+        let released = self.released_seq();
+        while self.views.len() > 1 && self.views[1].action_seq <= released {
+            self.views.pop_front();
+        }
+    }
+    /// The latest view at least `delay` moves old. Until the game has gone that far, the
+    /// opening view, which shows nothing the players chose.
+    pub fn get_view(&self) -> Option<&PlayerView> {
+        self.views
+            .front()
+            .filter(|view| view.action_seq <= self.released_seq())
+    }
+    fn released_seq(&self) -> u64 {
+        let newest = self.views.back().map_or(0, |view| view.action_seq);
+        newest.saturating_sub(self.delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayedViews;
+    use crate::game::test_game;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_delayed_views() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut game = test_game(&["a", "b"]);
+        let mut views = DelayedViews::new(2);
+        views.push(&game);
+        assert_eq!(views.get_view().unwrap().action_seq, 0);
+        for _ in 0..3 {
+            let player = game
+                .castles
+                .keys()
+                .find(|s| game.is_turn_player(s))
+                .unwrap()
+                .clone();
+            game = game.time_out_with_rng(&player, &mut rng).unwrap();
+            views.push(&game);
+        }
+        assert_eq!(views.get_view().unwrap().action_seq, 1);
+        assert_eq!(views.views.len(), 3);
+    }
+}
@@ -0,0 +1,191 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, convert::TryFrom, fmt, str::FromStr};
+use thiserror::Error;
+
+use crate::error::{ErrorBody, ErrorCode};
+
+// Words that are hard to mishear or misspell, 32 of each
+const ADJECTIVES: [&str; 32] = [
+    "BOLD", "BRAVE", "CALM", "DARK", "EAGER", "FAST", "FIERCE", "GENTLE", "GOLDEN", "GRAND",
+    "HAPPY", "HIDDEN", "JOLLY", "KIND", "LOYAL", "LUCKY", "MIGHTY", "NOBLE", "PROUD", "QUIET",
+    "RAPID", "ROYAL", "SILVER", "SLY", "STEADY", "SWIFT", "TALL", "TIDY", "VIVID", "WILD", "WISE",
+    "YOUNG",
+];
+const NOUNS: [&str; 32] = [
+    "ANVIL", "ARCH", "BANNER", "BRIDGE", "CANDLE", "CELLAR", "CROWN", "DRAGON", "DUNGEON",
+    "FALCON", "FORGE", "GARDEN", "GATE", "HALL", "HARBOR", "KNIGHT", "LANTERN", "MOAT", "ORCHARD",
+    "PALACE", "QUEEN", "RAVEN", "SHIELD", "SPIRE", "STABLE", "THRONE", "TOWER", "TURRET", "VAULT",
+    "WALL", "WELL", "WIZARD",
+];
+const NUMBERS: std::ops::RangeInclusive<u8> = 10..=99;
+/// Attempts at finding a code no live invite uses before giving up.
+const ATTEMPTS: usize = 64;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum InviteError {
+    #[error("{0} is not an invite code")]
+    Malformed(String),
+    #[error("No game uses invite code {0}")]
+    Unknown(InviteCode),
+    #[error("Invite code {0} has expired")]
+    Expired(InviteCode),
+}
+
+impl InviteError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            InviteError::Malformed(_) => ErrorCode::MalformedInput,
+            InviteError::Unknown(_) => ErrorCode::UnknownInvite,
+            InviteError::Expired(_) => ErrorCode::ExpiredInvite,
+        }
+    }
+    pub fn to_body(&self) -> ErrorBody {
+        ErrorBody::new(self.code(), self.to_string())
+    }
+}
+
+/// Join code such as `BRAVE-TOWER-42`, short enough to read out loud. Parsing ignores case
+/// and accepts spaces or underscores between the parts.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct InviteCode(String);
+
+impl InviteCode {
+    pub fn generate<R: Rng + ?Sized>(rng: &mut R) -> InviteCode {
+        InviteCode(format!(
+            "{}-{}-{}",
+            ADJECTIVES.choose(rng).unwrap(),
+            NOUNS.choose(rng).unwrap(),
+            rng.gen_range(NUMBERS)
+        ))
+    }
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for InviteCode {
+    type Err = InviteError;
+    fn from_str(s: &str) -> Result<InviteCode, InviteError> {
+        let normalized = s.trim().to_ascii_uppercase();
+        let parts: Vec<&str> = normalized
+            .split(|c: char| c == '-' || c == '_' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .collect();
+        match parts.as_slice() {
+            [adjective, noun, number]
+                if ADJECTIVES.contains(adjective)
+                    && NOUNS.contains(noun)
+                    && matches!(number.parse(), Ok(n) if NUMBERS.contains(&n)) =>
+            {
+                Ok(InviteCode(parts.join("-")))
+            }
+            _ => Err(InviteError::Malformed(s.to_string())),
+        }
+    }
+}
+
+impl TryFrom<String> for InviteCode {
+    type Error = InviteError;
+    fn try_from(s: String) -> Result<InviteCode, InviteError> {
+        s.parse()
+    }
+}
+
+impl From<InviteCode> for String {
+    fn from(code: InviteCode) -> String {
+        code.0
+    }
+}
+
+impl fmt::Display for InviteCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Invite codes handed out for game ids, each valid until its expiry time. Times are
+/// whatever clock the caller uses, such as Unix seconds.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InviteCodes<Id> {
+    codes: BTreeMap<InviteCode, Invite<Id>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Invite<Id> {
+    id: Id,
+    expires_at: u64,
+}
+
+impl<Id> InviteCodes<Id> {
+    pub fn new() -> InviteCodes<Id> {
+        InviteCodes {
+            codes: BTreeMap::new(),
+        }
+    }
+    /// Hand out a code for `id` valid until `expires_at`. Codes of expired invites are
+    /// reused. None when no free code was found, which only happens when most codes are live.
+    pub fn issue<R: Rng + ?Sized>(
+        &mut self,
+        id: Id,
+        now: u64,
+        expires_at: u64,
+        rng: &mut R,
+    ) -> Option<InviteCode> {
+        for _ in 0..ATTEMPTS {
+            let code = InviteCode::generate(rng);
+            if matches!(self.codes.get(&code), Some(invite) if invite.expires_at > now) {
+                continue;
+            }
+            self.codes.insert(code.clone(), Invite { id, expires_at });
+            return Some(code);
+        }
+        None
+    }
+    /// Id the code typed in by a player was issued for.
+    pub fn resolve(&self, code: &str, now: u64) -> Result<&Id, InviteError> {
+        let code: InviteCode = code.parse()?;
+        match self.codes.get(&code) {
+            Some(invite) if invite.expires_at > now => Ok(&invite.id),
+            Some(_) => Err(InviteError::Expired(code)),
+            None => Err(InviteError::Unknown(code)),
+        }
+    }
+    pub fn revoke(&mut self, code: &InviteCode) -> Option<Id> {
+        self.codes.remove(code).map(|invite| invite.id)
+    }
+    /// Forget expired invites.
+    pub fn purge(&mut self, now: u64) {
+        self.codes.retain(|_, invite| invite.expires_at > now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InviteCode, InviteCodes, InviteError};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    #[test]
+    fn test_invite_codes() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut invites = InviteCodes::new();
+        let code = invites.issue(42u32, 0, 100, &mut rng).unwrap();
+        let spoken = code.as_str().to_lowercase().replace('-', " ");
+        assert_eq!(invites.resolve(&spoken, 50), Ok(&42));
+        assert_eq!(
+            invites.resolve(code.as_str(), 100),
+            Err(InviteError::Expired(code.clone()))
+        );
+        assert!(matches!(
+            invites.resolve("BRAVE-TOWER-7", 0),
+            Err(InviteError::Malformed(_))
+        ));
+        assert!(matches!(
+            invites.resolve(&InviteCode::generate(&mut rng).to_string(), 0),
+            Err(InviteError::Unknown(_))
+        ));
+        invites.purge(100);
+        assert_eq!(invites.revoke(&code), None);
+    }
+}
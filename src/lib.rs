@@ -1,39 +1,75 @@
+// `tracing` events that compile to nothing without the `log` feature
+macro_rules! log_debug {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        tracing::debug!($($arg)*);
+    };
+}
+
+macro_rules! log_info {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        tracing::info!($($arg)*);
+    };
+}
+
+#[cfg(feature = "binary")]
+pub mod binary;
+#[cfg(feature = "bundled-cards")]
+pub mod bundled;
+pub mod castle;
+#[cfg(feature = "cbor")]
+pub mod cbor;
 pub mod disaster;
+pub mod error;
+#[cfg(feature = "ffi")]
+pub mod ffi;
 pub mod game;
+pub mod invite;
+pub mod load;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod pack;
+pub mod pos;
+#[cfg(feature = "protobuf")]
+pub mod proto;
+pub mod rating;
+pub mod room;
+pub mod save;
+#[cfg(feature = "schema")]
+pub mod schema;
+pub mod stats;
+pub mod tournament;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "watch")]
+pub mod watch;
 
-use disaster::Disaster;
-use disastle_castle_rust::Room;
-pub use ron;
-use std::{
-    fs::File,
-    io::{self, Read},
-    path::Path,
-    result,
+pub use error::{ErrorBody, ErrorCode};
+#[cfg(feature = "fs")]
+pub use load::{
+    load_disasters, load_pack, load_room_card_files, load_room_cards, load_rooms, load_thrones,
 };
+pub use load::{
+    load_disasters_from_reader, load_disasters_from_reader_as, load_disasters_from_str,
+    load_disasters_from_str_as, load_pack_from_str, load_pack_from_str_as,
+    load_room_cards_from_reader, load_room_cards_from_reader_as, load_room_cards_from_str,
+    load_room_cards_from_str_as, load_rooms_from_reader, load_rooms_from_reader_as,
+    load_rooms_from_str, load_rooms_from_str_as, load_thrones_from_reader,
+    load_thrones_from_reader_as, load_thrones_from_str, load_thrones_from_str_as, CardFormat,
+    LoadError,
+};
+pub use pos::{Direction, Pos};
+pub use ron;
+#[cfg(feature = "fs")]
+pub use save::{save_pack, save_room_cards, save_rooms};
 
-pub fn load_disasters(path: &Path) -> result::Result<Vec<Disaster>, io::Error> {
-    let mut file = File::open(path)?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
-    match ron::from_str(&content) {
-        Ok(disasters) => Ok(disasters),
-        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
-    }
-}
-
-pub fn load_rooms(path: &Path) -> result::Result<Vec<Room>, io::Error> {
-    let mut file = File::open(path)?;
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
-    match ron::from_str(&content) {
-        Ok(rooms) => Ok(rooms),
-        Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e)),
-    }
-}
-
-#[cfg(test)]
+#[cfg(all(test, feature = "fs"))]
 mod tests {
-    use crate::{load_disasters, load_rooms};
+    use crate::{
+        load_disasters, load_disasters_from_str, load_rooms, load_thrones,
+        load_thrones_from_reader, load_thrones_from_str,
+    };
     use std::path::Path;
     #[test]
     fn test_deserialize_disasters() {
@@ -46,12 +82,21 @@ mod tests {
     #[test]
     fn test_deserialize_thrones() {
         let path = Path::new("thrones.ron");
-        let result = load_rooms(&path);
+        let result = load_thrones(&path);
         assert!(result.is_ok());
         let disasters = result.unwrap();
         assert_eq!(disasters.len(), 10);
     }
     #[test]
+    fn test_load_from_str_and_reader() {
+        let content = std::fs::read_to_string("thrones.ron").unwrap();
+        let from_str = load_thrones_from_str(&content).unwrap();
+        assert_eq!(from_str, load_thrones(Path::new("thrones.ron")).unwrap());
+        let from_reader = load_thrones_from_reader(content.as_bytes()).unwrap();
+        assert_eq!(from_reader, from_str);
+        assert!(load_disasters_from_str(&content).is_err());
+    }
+    #[test]
     fn test_deserialize_rooms() {
         let path = Path::new("rooms.ron");
         let result = load_rooms(&path);
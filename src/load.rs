@@ -0,0 +1,612 @@
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    io::{self, Read},
+    result,
+};
+#[cfg(feature = "fs")]
+use std::{fs::File, path::Path};
+use thiserror::Error;
+
+use crate::{
+    disaster::Disaster,
+    error::{ErrorBody, ErrorCode},
+    pack::CardPack,
+    room::{CardError, RoomCard},
+};
+use disastle_castle_rust::{Connection, Room};
+
+type Result<T> = result::Result<T, LoadError>;
+
+/// Version of the card file and pack layout.
+///
+/// 1. Bare card lists and packs written before versions were recorded.
+pub const CARD_FORMAT_VERSION: u32 = 1;
+
+/// Name given to data loaded from a string or reader in error reports.
+const INPUT: &str = "input";
+
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Could not read {file}: {error}")]
+    Io {
+        file: String,
+        #[source]
+        error: io::Error,
+    },
+    #[error("Could not parse {file}: {message}")]
+    Parse { file: String, message: String },
+    #[error("{file} uses card format version {version} but only up to {supported} is supported")]
+    UnsupportedVersion {
+        file: String,
+        version: u32,
+        supported: u32,
+    },
+    #[error("Invalid card data: {}", describe(.0))]
+    Invalid(Vec<Problem>),
+}
+
+fn describe(problems: &[Problem]) -> String {
+    let messages: Vec<String> = problems.iter().map(|p| p.to_string()).collect();
+    messages.join("; ")
+}
+
+impl LoadError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            LoadError::Io { .. } => ErrorCode::UnreadableCardData,
+            LoadError::Parse { .. } => ErrorCode::MalformedCardData,
+            LoadError::UnsupportedVersion { .. } => ErrorCode::UnsupportedCardFormat,
+            LoadError::Invalid(_) => ErrorCode::InvalidCardData,
+        }
+    }
+    pub fn to_body(&self) -> ErrorBody {
+        let body = ErrorBody::new(self.code(), self.to_string());
+        match self {
+            LoadError::Io { file, .. } | LoadError::Parse { file, .. } => {
+                body.with_detail("file", file)
+            }
+            LoadError::UnsupportedVersion {
+                file,
+                version,
+                supported,
+            } => body
+                .with_detail("file", file)
+                .with_detail("version", version)
+                .with_detail("supported", supported),
+            LoadError::Invalid(problems) => body.with_detail("problems", problems.len()),
+        }
+    }
+}
+
+/// Lets callers working with `io::Result` keep using `?` on the loaders.
+impl From<LoadError> for io::Error {
+    fn from(error: LoadError) -> io::Error {
+        match error {
+            LoadError::Io { error, .. } => error,
+            error => io::Error::new(io::ErrorKind::InvalidData, error),
+        }
+    }
+}
+
+/// One problem in the card data, located by file and by index within the file's list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Problem {
+    pub file: String,
+    pub index: Option<usize>,
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.index {
+            Some(index) => write!(f, "{} entry {}: {}", self.file, index, self.kind),
+            None => write!(f, "{}: {}", self.file, self.kind),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum ProblemKind {
+    #[error("{0}")]
+    Card(CardError),
+    #[error("Room has no connections")]
+    NoConnections,
+    #[error("Room in a throne list is not a throne")]
+    NotAThrone,
+    #[error("No thrones")]
+    MissingThrones,
+    #[error("Disaster deals no damage and has no effect")]
+    ZeroDamage,
+    #[error("Id {0} is already used")]
+    DuplicateId(String),
+    #[error("Disaster name {0} is already used")]
+    DuplicateName(String),
+    #[error("Name given for unknown card {0}")]
+    UnknownName(String),
+}
+
+/// Collects problems for one file.
+struct Checker<'a> {
+    file: &'a str,
+    problems: Vec<Problem>,
+}
+
+impl<'a> Checker<'a> {
+    fn new(file: &'a str) -> Checker<'a> {
+        Checker {
+            file,
+            problems: Vec::new(),
+        }
+    }
+    fn report(&mut self, index: Option<usize>, kind: ProblemKind) {
+        self.problems.push(Problem {
+            file: self.file.to_string(),
+            index,
+            kind,
+        });
+    }
+    fn rooms(&mut self, rooms: &[Room]) {
+        for (index, room) in rooms.iter().enumerate() {
+            if crate::castle::connections(room)
+                .iter()
+                .all(|c| *c == Connection::None)
+            {
+                self.report(Some(index), ProblemKind::NoConnections);
+            }
+        }
+    }
+    fn thrones(&mut self, thrones: &[Room]) {
+        for (index, room) in thrones.iter().enumerate() {
+            if !room.throne {
+                self.report(Some(index), ProblemKind::NotAThrone);
+            }
+        }
+        if !thrones.iter().any(|room| room.throne) {
+            self.report(None, ProblemKind::MissingThrones);
+        }
+    }
+    fn disasters(&mut self, disasters: &[Disaster]) {
+        let mut names = BTreeSet::new();
+        for (index, disaster) in disasters.iter().enumerate() {
+            let harmless = [&disaster.diamond, &disaster.cross, &disaster.moon]
+                .iter()
+                .all(|c| c.multiplier == 0 && c.addition == 0 && c.formula.is_none());
+            if harmless && disaster.effects.is_empty() && disaster.target.is_none() {
+                self.report(Some(index), ProblemKind::ZeroDamage);
+            }
+            if !names.insert(disaster.name.as_str()) {
+                self.report(
+                    Some(index),
+                    ProblemKind::DuplicateName(disaster.name.clone()),
+                );
+            }
+        }
+    }
+    /// Check every card and that its id is not in `ids`, which may hold ids from other files.
+    fn cards(&mut self, cards: &[RoomCard], ids: &mut BTreeSet<String>) {
+        for (index, card) in cards.iter().enumerate() {
+            if let Err(errors) = card.validate(index) {
+                for error in errors {
+                    self.report(Some(index), ProblemKind::Card(error));
+                }
+            }
+            if !card.id.is_empty() && !ids.insert(card.id.clone()) {
+                self.report(Some(index), ProblemKind::DuplicateId(card.id.clone()));
+            }
+        }
+    }
+}
+
+/// Every problem in `pack`, reported against `file` with the list name appended.
+pub fn check_pack(file: &str, pack: &CardPack) -> Vec<Problem> {
+    let (thrones, rooms, disasters) = (
+        format!("{} thrones", file),
+        format!("{} rooms", file),
+        format!("{} disasters", file),
+    );
+    let mut problems = Vec::new();
+    let mut checker = Checker::new(&thrones);
+    checker.thrones(&pack.thrones);
+    problems.append(&mut checker.problems);
+    let mut checker = Checker::new(&rooms);
+    checker.cards(&pack.rooms, &mut BTreeSet::new());
+    problems.append(&mut checker.problems);
+    let mut checker = Checker::new(&disasters);
+    checker.disasters(&pack.disasters);
+    problems.append(&mut checker.problems);
+    let keys: BTreeSet<&str> = pack
+        .rooms
+        .iter()
+        .map(|card| card.id.as_str())
+        .chain(pack.thrones.iter().map(|room| room.name.as_str()))
+        .chain(pack.disasters.iter().map(|disaster| disaster.name.as_str()))
+        .collect();
+    let names = format!("{} names", file);
+    let mut checker = Checker::new(&names);
+    for table in pack.names.values() {
+        for key in table.keys() {
+            if !keys.contains(key.as_str()) {
+                checker.report(None, ProblemKind::UnknownName(key.clone()));
+            }
+        }
+    }
+    problems.append(&mut checker.problems);
+    problems
+}
+
+fn finish<T>(value: T, problems: Vec<Problem>) -> Result<T> {
+    if problems.is_empty() {
+        Ok(value)
+    } else {
+        Err(LoadError::Invalid(problems))
+    }
+}
+
+/// Syntax of a card file. File loaders pick it from the extension, falling back to RON.
+///
+/// TOML has no top-level arrays, so TOML card lists are always written in the versioned
+/// layout, with `[[cards]]` tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CardFormat {
+    Ron,
+    Json,
+    /// Needs the `toml` feature.
+    Toml,
+    /// Needs the `yaml` feature.
+    Yaml,
+}
+
+impl CardFormat {
+    #[cfg(feature = "fs")]
+    pub fn from_path(path: &Path) -> Option<CardFormat> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "ron" => Some(CardFormat::Ron),
+            "json" => Some(CardFormat::Json),
+            "toml" => Some(CardFormat::Toml),
+            "yaml" | "yml" => Some(CardFormat::Yaml),
+            _ => None,
+        }
+    }
+    fn parse<T: DeserializeOwned>(self, content: &str) -> result::Result<T, String> {
+        match self {
+            CardFormat::Ron => ron::from_str(content).map_err(|e| e.to_string()),
+            CardFormat::Json => serde_json::from_str(content).map_err(|e| e.to_string()),
+            #[cfg(feature = "toml")]
+            CardFormat::Toml => toml::from_str(content).map_err(|e| e.to_string()),
+            #[cfg(not(feature = "toml"))]
+            CardFormat::Toml => Err("TOML card files need the toml feature".to_string()),
+            #[cfg(feature = "yaml")]
+            CardFormat::Yaml => serde_yaml::from_str(content).map_err(|e| e.to_string()),
+            #[cfg(not(feature = "yaml"))]
+            CardFormat::Yaml => Err("YAML card files need the yaml feature".to_string()),
+        }
+    }
+}
+
+/// Card list with its format version, written `(format_version: 1, cards: [...])` in RON.
+/// Bare lists are read as version 1.
+#[derive(Deserialize)]
+struct CardFile<T> {
+    #[serde(default = "first_version")]
+    format_version: u32,
+    cards: Vec<T>,
+}
+
+fn first_version() -> u32 {
+    1
+}
+
+fn check_version(file: &str, version: u32) -> Result<()> {
+    if version == 0 || version > CARD_FORMAT_VERSION {
+        Err(LoadError::UnsupportedVersion {
+            file: file.to_string(),
+            version,
+            supported: CARD_FORMAT_VERSION,
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn parse<T: DeserializeOwned>(file: &str, format: CardFormat, content: &str) -> Result<T> {
+    format.parse(content).map_err(|message| LoadError::Parse {
+        file: file.to_string(),
+        message,
+    })
+}
+
+fn parse_list<T: DeserializeOwned>(
+    file: &str,
+    format: CardFormat,
+    content: &str,
+) -> Result<Vec<T>> {
+    let versioned = match format {
+        CardFormat::Toml => parse::<CardFile<T>>(file, format, content)?,
+        // A bare list's own error is the more helpful one when neither layout matches
+        _ => match format.parse::<CardFile<T>>(content) {
+            Ok(versioned) => versioned,
+            Err(_) => CardFile {
+                format_version: first_version(),
+                cards: parse(file, format, content)?,
+            },
+        },
+    };
+    check_version(file, versioned.format_version)?;
+    Ok(versioned.cards)
+}
+
+fn read<R: Read>(file: &str, mut reader: R) -> Result<String> {
+    let mut content = String::new();
+    match reader.read_to_string(&mut content) {
+        Ok(_) => Ok(content),
+        Err(error) => Err(LoadError::Io {
+            file: file.to_string(),
+            error,
+        }),
+    }
+}
+
+#[cfg(feature = "fs")]
+fn read_file(path: &Path) -> Result<(String, CardFormat, String)> {
+    let file = path.display().to_string();
+    let content = File::open(path)
+        .map_err(|error| LoadError::Io {
+            file: file.clone(),
+            error,
+        })
+        .and_then(|f| read(&file, f))?;
+    let format = CardFormat::from_path(path).unwrap_or(CardFormat::Ron);
+    Ok((file, format, content))
+}
+
+fn disasters(file: &str, format: CardFormat, content: &str) -> Result<Vec<Disaster>> {
+    let disasters: Vec<Disaster> = parse_list(file, format, content)?;
+    let mut checker = Checker::new(file);
+    checker.disasters(&disasters);
+    finish(disasters, checker.problems)
+}
+
+pub fn load_disasters_from_str(content: &str) -> Result<Vec<Disaster>> {
+    disasters(INPUT, CardFormat::Ron, content)
+}
+
+pub fn load_disasters_from_str_as(content: &str, format: CardFormat) -> Result<Vec<Disaster>> {
+    disasters(INPUT, format, content)
+}
+
+pub fn load_disasters_from_reader<R: Read>(reader: R) -> Result<Vec<Disaster>> {
+    load_disasters_from_reader_as(reader, CardFormat::Ron)
+}
+
+pub fn load_disasters_from_reader_as<R: Read>(
+    reader: R,
+    format: CardFormat,
+) -> Result<Vec<Disaster>> {
+    disasters(INPUT, format, &read(INPUT, reader)?)
+}
+
+#[cfg(feature = "fs")]
+pub fn load_disasters(path: &Path) -> Result<Vec<Disaster>> {
+    let (file, format, content) = read_file(path)?;
+    disasters(&file, format, &content)
+}
+
+fn rooms(file: &str, format: CardFormat, content: &str) -> Result<Vec<Room>> {
+    let rooms: Vec<Room> = parse_list(file, format, content)?;
+    let mut checker = Checker::new(file);
+    checker.rooms(&rooms);
+    finish(rooms, checker.problems)
+}
+
+pub fn load_rooms_from_str(content: &str) -> Result<Vec<Room>> {
+    rooms(INPUT, CardFormat::Ron, content)
+}
+
+pub fn load_rooms_from_str_as(content: &str, format: CardFormat) -> Result<Vec<Room>> {
+    rooms(INPUT, format, content)
+}
+
+pub fn load_rooms_from_reader<R: Read>(reader: R) -> Result<Vec<Room>> {
+    load_rooms_from_reader_as(reader, CardFormat::Ron)
+}
+
+pub fn load_rooms_from_reader_as<R: Read>(reader: R, format: CardFormat) -> Result<Vec<Room>> {
+    rooms(INPUT, format, &read(INPUT, reader)?)
+}
+
+#[cfg(feature = "fs")]
+pub fn load_rooms(path: &Path) -> Result<Vec<Room>> {
+    let (file, format, content) = read_file(path)?;
+    rooms(&file, format, &content)
+}
+
+fn thrones(file: &str, format: CardFormat, content: &str) -> Result<Vec<Room>> {
+    let thrones: Vec<Room> = parse_list(file, format, content)?;
+    let mut checker = Checker::new(file);
+    checker.thrones(&thrones);
+    finish(thrones, checker.problems)
+}
+
+/// Load rooms that must all be thrones, at least one of them.
+pub fn load_thrones_from_str(content: &str) -> Result<Vec<Room>> {
+    thrones(INPUT, CardFormat::Ron, content)
+}
+
+pub fn load_thrones_from_str_as(content: &str, format: CardFormat) -> Result<Vec<Room>> {
+    thrones(INPUT, format, content)
+}
+
+pub fn load_thrones_from_reader<R: Read>(reader: R) -> Result<Vec<Room>> {
+    load_thrones_from_reader_as(reader, CardFormat::Ron)
+}
+
+pub fn load_thrones_from_reader_as<R: Read>(reader: R, format: CardFormat) -> Result<Vec<Room>> {
+    thrones(INPUT, format, &read(INPUT, reader)?)
+}
+
+#[cfg(feature = "fs")]
+pub fn load_thrones(path: &Path) -> Result<Vec<Room>> {
+    let (file, format, content) = read_file(path)?;
+    thrones(&file, format, &content)
+}
+
+fn room_cards(
+    file: &str,
+    format: CardFormat,
+    content: &str,
+    ids: &mut BTreeSet<String>,
+) -> Result<Vec<RoomCard>> {
+    let cards: Vec<RoomCard> = parse_list(file, format, content)?;
+    let mut checker = Checker::new(file);
+    checker.cards(&cards, ids);
+    finish(cards, checker.problems)
+}
+
+/// Load rooms with their card metadata, rejecting invalid cards. See `room::RoomCard` for
+/// the format.
+pub fn load_room_cards_from_str(content: &str) -> Result<Vec<RoomCard>> {
+    load_room_cards_from_str_as(content, CardFormat::Ron)
+}
+
+pub fn load_room_cards_from_str_as(content: &str, format: CardFormat) -> Result<Vec<RoomCard>> {
+    room_cards(INPUT, format, content, &mut BTreeSet::new())
+}
+
+pub fn load_room_cards_from_reader<R: Read>(reader: R) -> Result<Vec<RoomCard>> {
+    load_room_cards_from_reader_as(reader, CardFormat::Ron)
+}
+
+pub fn load_room_cards_from_reader_as<R: Read>(
+    reader: R,
+    format: CardFormat,
+) -> Result<Vec<RoomCard>> {
+    load_room_cards_from_str_as(&read(INPUT, reader)?, format)
+}
+
+#[cfg(feature = "fs")]
+pub fn load_room_cards(path: &Path) -> Result<Vec<RoomCard>> {
+    let (file, format, content) = read_file(path)?;
+    room_cards(&file, format, &content, &mut BTreeSet::new())
+}
+
+/// Load the room cards of several files, also rejecting ids used in more than one file.
+/// Every file is checked before failing, so the error lists the problems of all of them.
+#[cfg(feature = "fs")]
+pub fn load_room_card_files(paths: &[&Path]) -> Result<Vec<RoomCard>> {
+    let mut ids = BTreeSet::new();
+    let mut cards = Vec::new();
+    let mut problems = Vec::new();
+    for path in paths {
+        let (file, format, content) = read_file(path)?;
+        match room_cards(&file, format, &content, &mut ids) {
+            Ok(mut file_cards) => cards.append(&mut file_cards),
+            Err(LoadError::Invalid(mut file_problems)) => problems.append(&mut file_problems),
+            Err(e) => return Err(e),
+        }
+    }
+    finish(cards, problems)
+}
+
+fn pack(file: &str, format: CardFormat, content: &str) -> Result<CardPack> {
+    let pack: CardPack = parse(file, format, content)?;
+    check_version(file, pack.format_version)?;
+    let problems = check_pack(file, &pack);
+    finish(pack, problems)
+}
+
+/// Load a RON card pack manifest, rejecting invalid cards. See `pack::CardPack` for the
+/// format.
+pub fn load_pack_from_str(content: &str) -> Result<CardPack> {
+    pack(INPUT, CardFormat::Ron, content)
+}
+
+pub fn load_pack_from_str_as(content: &str, format: CardFormat) -> Result<CardPack> {
+    pack(INPUT, format, content)
+}
+
+/// Load a card pack manifest in the format given by its extension, RON if unknown.
+#[cfg(feature = "fs")]
+pub fn load_pack(path: &Path) -> Result<CardPack> {
+    let (file, format, content) = read_file(path)?;
+    pack(&file, format, &content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        load_disasters_from_str, load_thrones_from_str, load_thrones_from_str_as, CardFormat,
+        LoadError, ProblemKind,
+    };
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn test_load_formats_and_versions() {
+        let thrones = load_thrones_from_str(include_str!("../thrones.ron")).unwrap();
+        let json = serde_json::to_string(&thrones).unwrap();
+        assert_eq!(
+            load_thrones_from_str_as(&json, CardFormat::Json).unwrap(),
+            thrones
+        );
+
+        let ron = ron::to_string(&thrones).unwrap();
+        let versioned = format!("(format_version: 1, cards: {})", ron);
+        assert_eq!(load_thrones_from_str(&versioned).unwrap(), thrones);
+        let newer = format!("(format_version: 9, cards: {})", ron);
+        assert!(matches!(
+            load_thrones_from_str(&newer),
+            Err(LoadError::UnsupportedVersion { version: 9, .. })
+        ));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_format_from_path() {
+        use std::path::Path;
+        assert_eq!(
+            CardFormat::from_path(Path::new("expansion.YML")),
+            Some(CardFormat::Yaml)
+        );
+        assert_eq!(CardFormat::from_path(Path::new("thrones")), None);
+    }
+
+    #[test]
+    fn test_problems_are_listed() {
+        let thrones = load_thrones_from_str(
+            r#"[Room(
+                throne: false,
+                treasure: 0,
+                name: "Hall",
+                rotation: 0,
+                connections: (None, None, None, None)
+            )]"#,
+        );
+        let problems = match thrones {
+            Err(LoadError::Invalid(problems)) => problems,
+            other => panic!("unexpected {:?}", other),
+        };
+        let kinds: Vec<_> = problems.iter().map(|p| (p.index, p.kind.clone())).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (Some(0), ProblemKind::NotAThrone),
+                (None, ProblemKind::MissingThrones)
+            ]
+        );
+
+        let calm = "DamageCalculation(multiplier: 0, addition: 0)";
+        let disasters = format!(
+            "[Disaster(name: \"Calm\", diamond: {0}, cross: {0}, moon: {0})]",
+            calm
+        );
+        let error = load_disasters_from_str(&disasters).unwrap_err();
+        assert_eq!(error.code(), ErrorCode::InvalidCardData);
+        assert_eq!(
+            error.to_string(),
+            "Invalid card data: input entry 0: Disaster deals no damage and has no effect"
+        );
+        let error = load_disasters_from_str("[").unwrap_err();
+        assert_eq!(error.code(), ErrorCode::MalformedCardData);
+    }
+}
@@ -0,0 +1,40 @@
+//! MessagePack encoding of player views, actions and error bodies for clients that prefer a
+//! binary wire format. Structs are encoded as maps so field names survive across languages.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+pub use rmp_serde::{decode::Error as DecodeError, encode::Error as EncodeError};
+
+pub fn encode<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    rmp_serde::to_vec_named(value)
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, DecodeError> {
+    rmp_serde::from_slice(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, encode};
+    use crate::{
+        error::ErrorBody,
+        game::{test_game, GameError, PlayerSecret, PlayerView},
+    };
+
+    #[test]
+    fn test_player_view_round_trip() {
+        let game = test_game(&["a", "b", "c"]);
+        let view = game.player_view(&PlayerSecret::from("a"));
+        let decoded: PlayerView = decode(&encode(&view).unwrap()).unwrap();
+        assert_eq!(decoded, view);
+    }
+    #[test]
+    fn test_error_round_trip() {
+        let error = GameError::InvalidShopIndex {
+            index: 7,
+            shop_len: 5,
+        };
+        let decoded: ErrorBody = decode(&encode(&error).unwrap()).unwrap();
+        assert_eq!(decoded, error.to_body());
+    }
+}
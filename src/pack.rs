@@ -0,0 +1,333 @@
+use disastle_castle_rust::Room;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use thiserror::Error;
+
+use crate::{
+    disaster::Disaster,
+    game::GameSetting,
+    load::{self, LoadError},
+    room::RoomCard,
+};
+
+/// Expansion or base set distributed as one manifest: its cards, where it comes from and
+/// how many of them a game uses.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CardPack {
+    /// Layout version, see `load::CARD_FORMAT_VERSION`. Packs without one are version 1.
+    #[serde(default = "one")]
+    pub format_version: u32,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub author: Option<String>,
+    pub thrones: Vec<Room>,
+    pub rooms: Vec<RoomCard>,
+    pub disasters: Vec<Disaster>,
+    #[serde(default = "five")]
+    pub num_safe: u8,
+    #[serde(default = "five")]
+    pub num_shop: u8,
+    #[serde(default = "six")]
+    pub num_disasters: u8,
+    /// Display names by locale, then by room card id, throne name or disaster name. Game
+    /// logic never reads them.
+    #[serde(default)]
+    pub names: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+/// How `CardPack::merge` handles an incoming card whose id or name is already taken by a
+/// different card. Identical cards are always merged silently.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MergeMode {
+    /// Refuse to merge, leaving the pack unchanged.
+    #[default]
+    Strict,
+    /// Keep the card already in the pack and drop the incoming one.
+    KeepExisting,
+    /// Give incoming room cards the id `<pack name>/<id>`. Thrones and disasters are
+    /// identified by name, which game logic relies on, so the existing ones are kept.
+    Remap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CardKind {
+    Throne,
+    Room,
+    Disaster,
+}
+
+/// Incoming card colliding with a different card already in the pack.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    pub kind: CardKind,
+    /// Room card id, or throne or disaster name.
+    pub key: String,
+    /// New id of the incoming room card under `MergeMode::Remap`.
+    pub renamed: Option<String>,
+}
+
+#[derive(Debug, Error)]
+#[error("{} cards conflict with cards already in the pack", .0.len())]
+pub struct MergeError(pub Vec<Conflict>);
+
+fn one() -> u32 {
+    1
+}
+
+fn five() -> u8 {
+    5
+}
+
+fn six() -> u8 {
+    6
+}
+
+impl CardPack {
+    /// Every problem in the pack's cards, reported against the pack name.
+    pub fn validate(&self) -> Result<(), LoadError> {
+        let problems = load::check_pack(&self.name, self);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(LoadError::Invalid(problems))
+        }
+    }
+    /// Name of the card `key` in `locale`, trying the bare language when a region is given
+    /// (`de` for `de-AT`).
+    pub fn display_name(&self, locale: &str, key: &str) -> Option<&str> {
+        let language = locale.split(&['-', '_'][..]).next();
+        let table = self
+            .names
+            .get(locale)
+            .or_else(|| self.names.get(language?))?;
+        table.get(key).map(String::as_str)
+    }
+    /// Name of `room` in `locale`, found through the id of the card it was dealt from, in any
+    /// rotation. Thrones have no card and are looked up by name. Falls back to the room's own name.
+    pub fn room_display_name<'a>(&'a self, locale: &str, room: &'a Room) -> &'a str {
+        let key = self
+            .rooms
+            .iter()
+            .find(|card| card.matches(room))
+            .map_or(room.name.as_str(), |card| card.id.as_str());
+        self.display_name(locale, key).unwrap_or(&room.name)
+    }
+    pub fn disaster_display_name<'a>(&'a self, locale: &str, disaster: &'a Disaster) -> &'a str {
+        self.display_name(locale, &disaster.name)
+            .unwrap_or(&disaster.name)
+    }
+    /// Add the cards and translations of `other`, keeping this pack's name, version and
+    /// counts. Returns the conflicts that `mode` resolved.
+    pub fn merge(&mut self, other: CardPack, mode: MergeMode) -> Result<Vec<Conflict>, MergeError> {
+        let mut merged = self.clone();
+        let mut conflicts = Vec::new();
+        let mut renames = BTreeMap::new();
+        for throne in other.thrones {
+            match merged.thrones.iter().find(|t| t.name == throne.name) {
+                Some(existing) if *existing == throne => {}
+                Some(_) => conflicts.push(Conflict {
+                    kind: CardKind::Throne,
+                    key: throne.name,
+                    renamed: None,
+                }),
+                None => merged.thrones.push(throne),
+            }
+        }
+        for mut card in other.rooms {
+            match merged.rooms.iter().find(|c| c.id == card.id) {
+                Some(existing) if *existing == card => {}
+                Some(_) => {
+                    let renamed = match mode {
+                        MergeMode::Remap => {
+                            let mut id = format!("{}/{}", other.name, card.id);
+                            let mut n = 2;
+                            while merged.rooms.iter().any(|c| c.id == id) {
+                                id = format!("{}/{}-{}", other.name, card.id, n);
+                                n += 1;
+                            }
+                            Some(id)
+                        }
+                        _ => None,
+                    };
+                    conflicts.push(Conflict {
+                        kind: CardKind::Room,
+                        key: card.id.clone(),
+                        renamed: renamed.clone(),
+                    });
+                    if let Some(id) = renamed {
+                        renames.insert(std::mem::replace(&mut card.id, id.clone()), id);
+                        merged.rooms.push(card);
+                    }
+                }
+                None => merged.rooms.push(card),
+            }
+        }
+        for disaster in other.disasters {
+            match merged.disasters.iter().find(|d| d.name == disaster.name) {
+                Some(existing) if *existing == disaster => {}
+                Some(_) => conflicts.push(Conflict {
+                    kind: CardKind::Disaster,
+                    key: disaster.name,
+                    renamed: None,
+                }),
+                None => merged.disasters.push(disaster),
+            }
+        }
+        if mode == MergeMode::Strict && !conflicts.is_empty() {
+            return Err(MergeError(conflicts));
+        }
+        for (locale, table) in other.names {
+            let merged_table = merged.names.entry(locale).or_default();
+            for (key, name) in table {
+                let key = renames.get(&key).cloned().unwrap_or(key);
+                merged_table.entry(key).or_insert(name);
+            }
+        }
+        *self = merged;
+        Ok(conflicts)
+    }
+    /// Setting dealing every card of the pack, with room copies and shields taken from the
+    /// room cards.
+    pub fn into_setting(self) -> GameSetting {
+        GameSetting {
+            num_safe: self.num_safe,
+            num_shop: self.num_shop,
+            num_disasters: self.num_disasters,
+            thrones: self.thrones.into_iter().collect(),
+            rooms: Default::default(),
+            disasters: self.disasters.into_iter().collect(),
+            copies: Vec::new(),
+            shields: Vec::new(),
+            disaster_order: Default::default(),
+            forecast_rounds: 0,
+            abort_votes_needed: None,
+        }
+        .with_cards(&self.rooms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CardKind, CardPack, MergeMode};
+    use crate::load::{LoadError, CARD_FORMAT_VERSION};
+    use crate::{
+        load_disasters_from_str, load_pack_from_str, load_rooms_from_str, load_thrones_from_str,
+        room::{RoomCard, RoomExt},
+    };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_pack_into_setting() {
+        let rooms = load_rooms_from_str(include_str!("../rooms.ron")).unwrap();
+        let pack = CardPack {
+            format_version: CARD_FORMAT_VERSION,
+            name: "Base".to_string(),
+            version: "1.0.0".to_string(),
+            author: None,
+            thrones: load_thrones_from_str(include_str!("../thrones.ron")).unwrap(),
+            rooms: rooms
+                .iter()
+                .enumerate()
+                .map(|(i, room)| RoomCard::new(i.to_string(), room.clone()))
+                .collect(),
+            disasters: load_disasters_from_str(include_str!("../disasters.ron")).unwrap(),
+            num_safe: 5,
+            num_shop: 5,
+            num_disasters: 6,
+            names: BTreeMap::new(),
+        };
+        let text = ron::to_string(&pack).unwrap();
+        assert_eq!(load_pack_from_str(&text).unwrap(), pack);
+        let newer = CardPack {
+            format_version: CARD_FORMAT_VERSION + 1,
+            ..pack.clone()
+        };
+        assert!(matches!(
+            load_pack_from_str(&ron::to_string(&newer).unwrap()),
+            Err(LoadError::UnsupportedVersion { .. })
+        ));
+        let json = serde_json::to_string(&pack).unwrap();
+        assert_eq!(serde_json::from_str::<CardPack>(&json).unwrap(), pack);
+
+        let setting = pack.into_setting();
+        assert_eq!(setting.rooms.len(), rooms.len());
+        assert_eq!(setting.thrones.len(), 10);
+        assert!(setting.copies.is_empty());
+    }
+    #[test]
+    fn test_display_names() {
+        let mut pack: CardPack = ron::from_str(
+            r#"(
+                name: "Tiny",
+                version: "1.0.0",
+                thrones: [],
+                rooms: [],
+                disasters: [],
+                names: {"de": {"base-0": "Saal"}},
+            )"#,
+        )
+        .unwrap();
+        let room = load_rooms_from_str(include_str!("../rooms.ron"))
+            .unwrap()
+            .remove(0);
+        pack.rooms = vec![RoomCard::new("base-0".to_string(), room.clone())];
+        assert_eq!(pack.room_display_name("de-AT", &room), "Saal");
+        assert_eq!(pack.room_display_name("fr", &room), room.name);
+        // Cards sharing a name keep their own translations, in any rotation
+        let mut twin = room.clone();
+        twin.treasure += 1;
+        pack.rooms
+            .push(RoomCard::new("base-1".to_string(), twin.clone()));
+        pack.names
+            .get_mut("de")
+            .unwrap()
+            .insert("base-1".to_string(), "Zwilling".to_string());
+        assert_eq!(pack.room_display_name("de", &twin), "Zwilling");
+        assert_eq!(pack.room_display_name("de", &room.rotated_right()), "Saal");
+
+        let disaster = load_disasters_from_str(include_str!("../disasters.ron"))
+            .unwrap()
+            .remove(0);
+        assert_eq!(pack.disaster_display_name("de", &disaster), disaster.name);
+    }
+    #[test]
+    fn test_merge_packs() {
+        let rooms = load_rooms_from_str(include_str!("../rooms.ron")).unwrap();
+        let pack = |name: &str, cards: &[(&str, usize)]| CardPack {
+            format_version: CARD_FORMAT_VERSION,
+            name: name.to_string(),
+            version: "1.0.0".to_string(),
+            author: None,
+            thrones: Vec::new(),
+            rooms: cards
+                .iter()
+                .map(|&(id, i)| RoomCard::new(id.to_string(), rooms[i].clone()))
+                .collect(),
+            disasters: Vec::new(),
+            num_safe: 5,
+            num_shop: 5,
+            num_disasters: 6,
+            names: BTreeMap::new(),
+        };
+        let mut base = pack("Base", &[("a", 0), ("b", 1)]);
+        let fan = pack("Fan", &[("a", 0), ("b", 2), ("c", 3)]);
+
+        let conflicts = base
+            .clone()
+            .merge(fan.clone(), MergeMode::Strict)
+            .unwrap_err();
+        assert_eq!(conflicts.0.len(), 1);
+        assert_eq!(conflicts.0[0].kind, CardKind::Room);
+
+        let mut kept = base.clone();
+        kept.merge(fan.clone(), MergeMode::KeepExisting).unwrap();
+        assert_eq!(kept.rooms.len(), 3);
+        assert_eq!(kept.rooms[1].room, rooms[1]);
+
+        let conflicts = base.merge(fan, MergeMode::Remap).unwrap();
+        assert_eq!(conflicts[0].renamed.as_deref(), Some("Fan/b"));
+        let ids: Vec<&str> = base.rooms.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["a", "b", "Fan/b", "c"]);
+    }
+}
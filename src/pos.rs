@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A grid position in a castle, `x` growing to the right and `y` growing upward.
+///
+/// Serializes as an `(x, y)` tuple so it stays wire-compatible with the positions used by
+/// `disastle_castle_rust`.
+#[derive(
+    Clone, Copy, Debug, Default, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize,
+)]
+#[serde(from = "(i32, i32)", into = "(i32, i32)")]
+pub struct Pos {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Pos {
+    pub const ORIGIN: Pos = Pos { x: 0, y: 0 };
+
+    pub fn new(x: i32, y: i32) -> Pos {
+        Pos { x, y }
+    }
+    pub fn offset(self, dx: i32, dy: i32) -> Pos {
+        Pos::new(self.x + dx, self.y + dy)
+    }
+    pub fn up(self) -> Pos {
+        self.offset(0, 1)
+    }
+    pub fn right(self) -> Pos {
+        self.offset(1, 0)
+    }
+    pub fn down(self) -> Pos {
+        self.offset(0, -1)
+    }
+    pub fn left(self) -> Pos {
+        self.offset(-1, 0)
+    }
+    /// The four adjacent positions in connection order: up, right, down, left.
+    pub fn neighbors(self) -> [Pos; 4] {
+        [self.up(), self.right(), self.down(), self.left()]
+    }
+    pub fn manhattan_distance(self, other: Pos) -> u32 {
+        ((self.x - other.x).abs() + (self.y - other.y).abs()) as u32
+    }
+    pub fn is_adjacent(self, other: Pos) -> bool {
+        self.manhattan_distance(other) == 1
+    }
+    pub fn step(self, direction: Direction) -> Pos {
+        self.neighbors()[direction.index()]
+    }
+}
+
+/// A side of a room, in the same order as room connections and `Pos::neighbors`.
+#[derive(Clone, Copy, Debug, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn index(self) -> usize {
+        self as usize
+    }
+    pub fn opposite(self) -> Direction {
+        Direction::ALL[(self.index() + 2) % 4]
+    }
+}
+
+impl From<(i32, i32)> for Pos {
+    fn from((x, y): (i32, i32)) -> Self {
+        Pos { x, y }
+    }
+}
+
+impl From<Pos> for (i32, i32) {
+    fn from(pos: Pos) -> Self {
+        (pos.x, pos.y)
+    }
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Direction, Pos};
+
+    #[test]
+    fn test_pos_geometry() {
+        let pos = Pos::new(2, -1);
+        assert_eq!(
+            pos.neighbors(),
+            [
+                Pos::new(2, 0),
+                Pos::new(3, -1),
+                Pos::new(2, -2),
+                Pos::new(1, -1)
+            ]
+        );
+        assert!(pos.neighbors().iter().all(|n| n.is_adjacent(pos)));
+        assert_eq!(pos.manhattan_distance(Pos::ORIGIN), 3);
+        for direction in Direction::ALL.iter() {
+            assert_eq!(pos.step(*direction).step(direction.opposite()), pos);
+        }
+        assert_eq!(Pos::from((2, -1)), pos);
+        assert_eq!(
+            ron::to_string(&pos).unwrap(),
+            ron::to_string(&(2, -1)).unwrap()
+        );
+    }
+}
@@ -0,0 +1,420 @@
+//! Prost messages mirroring `proto/disastle.proto`, with conversions from the crate's types.
+
+use std::{collections::BTreeMap, convert::TryFrom};
+use thiserror::Error;
+
+use crate::{
+    disaster, error::ErrorBody, game::GameState, game::PlayerSecret, game::PlayerView, pos,
+};
+use disastle_castle_rust as castle;
+
+#[derive(Clone, Copy, PartialEq, Eq, prost::Message)]
+pub struct Pos {
+    #[prost(sint32, tag = "1")]
+    pub x: i32,
+    #[prost(sint32, tag = "2")]
+    pub y: i32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+#[repr(i32)]
+pub enum ConnectionKind {
+    None = 0,
+    Diamond = 1,
+    Cross = 2,
+    Moon = 3,
+    Wild = 4,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Connection {
+    #[prost(enumeration = "ConnectionKind", tag = "1")]
+    pub kind: i32,
+    #[prost(bool, tag = "2")]
+    pub gold: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Room {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(bool, tag = "2")]
+    pub throne: bool,
+    #[prost(uint32, tag = "3")]
+    pub treasure: u32,
+    #[prost(uint32, tag = "4")]
+    pub rotation: u32,
+    #[prost(message, repeated, tag = "5")]
+    pub connections: Vec<Connection>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct PlacedRoom {
+    #[prost(message, optional, tag = "1")]
+    pub pos: Option<Pos>,
+    #[prost(message, optional, tag = "2")]
+    pub room: Option<Room>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Castle {
+    #[prost(message, repeated, tag = "1")]
+    pub rooms: Vec<PlacedRoom>,
+    #[prost(uint32, tag = "2")]
+    pub damage: u32,
+    #[prost(bool, tag = "3")]
+    pub lost: bool,
+    #[prost(uint32, tag = "4")]
+    pub treasure: u32,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct DamageCalculation {
+    #[prost(uint32, tag = "1")]
+    pub multiplier: u32,
+    #[prost(uint32, tag = "2")]
+    pub addition: u32,
+    /// Empty unless the damage is given by a formula.
+    #[prost(string, tag = "3")]
+    pub formula: String,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Disaster {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(message, optional, tag = "2")]
+    pub diamond: Option<DamageCalculation>,
+    #[prost(message, optional, tag = "3")]
+    pub cross: Option<DamageCalculation>,
+    #[prost(message, optional, tag = "4")]
+    pub moon: Option<DamageCalculation>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Place {
+    #[prost(uint32, tag = "1")]
+    pub shop_index: u32,
+    #[prost(message, optional, tag = "2")]
+    pub pos: Option<Pos>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Move {
+    #[prost(message, optional, tag = "1")]
+    pub from: Option<Pos>,
+    #[prost(message, optional, tag = "2")]
+    pub to: Option<Pos>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Swap {
+    #[prost(message, optional, tag = "1")]
+    pub pos1: Option<Pos>,
+    #[prost(message, optional, tag = "2")]
+    pub pos2: Option<Pos>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Discard {
+    #[prost(message, optional, tag = "1")]
+    pub pos: Option<Pos>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Action {
+    #[prost(oneof = "action::Kind", tags = "1, 2, 3, 4")]
+    pub kind: Option<action::Kind>,
+}
+
+pub mod action {
+    #[derive(Clone, PartialEq, prost::Oneof)]
+    pub enum Kind {
+        #[prost(message, tag = "1")]
+        Place(super::Place),
+        #[prost(message, tag = "2")]
+        Move(super::Move),
+        #[prost(message, tag = "3")]
+        Swap(super::Swap),
+        #[prost(message, tag = "4")]
+        Discard(super::Discard),
+    }
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct GameView {
+    #[prost(uint32, optional, tag = "1")]
+    pub seat: Option<u32>,
+    #[prost(message, repeated, tag = "2")]
+    pub castles: Vec<Castle>,
+    #[prost(uint32, repeated, tag = "3")]
+    pub turn_order: Vec<u32>,
+    #[prost(uint32, repeated, tag = "4")]
+    pub to_act: Vec<u32>,
+    #[prost(message, repeated, tag = "5")]
+    pub shop: Vec<Room>,
+    #[prost(message, repeated, tag = "6")]
+    pub discard: Vec<Room>,
+    #[prost(message, repeated, tag = "7")]
+    pub previous_disasters: Vec<Disaster>,
+    #[prost(message, repeated, tag = "8")]
+    pub queued_disasters: Vec<Disaster>,
+    #[prost(uint32, tag = "9")]
+    pub deck_size: u32,
+    #[prost(uint32, tag = "10")]
+    pub round: u32,
+    #[prost(uint64, tag = "11")]
+    pub action_seq: u64,
+    #[prost(message, repeated, tag = "12")]
+    pub possible_actions: Vec<Action>,
+    #[prost(bool, tag = "13")]
+    pub is_over: bool,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+pub struct Error {
+    #[prost(string, tag = "1")]
+    pub code: String,
+    #[prost(uint32, tag = "2")]
+    pub numeric_code: u32,
+    #[prost(string, tag = "3")]
+    pub message: String,
+    #[prost(btree_map = "string, string", tag = "4")]
+    pub details: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Error)]
+pub enum ProtoError {
+    #[error("Message is missing required field {0}")]
+    MissingField(&'static str),
+}
+
+impl From<pos::Pos> for Pos {
+    fn from(pos: pos::Pos) -> Self {
+        Pos { x: pos.x, y: pos.y }
+    }
+}
+
+impl From<castle::Pos> for Pos {
+    fn from(pos: castle::Pos) -> Self {
+        pos::Pos::from(pos).into()
+    }
+}
+
+impl From<Pos> for castle::Pos {
+    fn from(pos: Pos) -> Self {
+        (pos.x, pos.y)
+    }
+}
+
+impl From<&castle::Connection> for Connection {
+    fn from(connection: &castle::Connection) -> Self {
+        let (kind, gold) = match connection {
+            castle::Connection::None => (ConnectionKind::None, false),
+            castle::Connection::Diamond(gold) => (ConnectionKind::Diamond, *gold),
+            castle::Connection::Cross(gold) => (ConnectionKind::Cross, *gold),
+            castle::Connection::Moon(gold) => (ConnectionKind::Moon, *gold),
+            castle::Connection::Wild => (ConnectionKind::Wild, false),
+        };
+        Connection {
+            kind: kind as i32,
+            gold,
+        }
+    }
+}
+
+impl From<&castle::Room> for Room {
+    fn from(room: &castle::Room) -> Self {
+        let (up, right, down, left) = &room.connections;
+        Room {
+            name: room.name.clone(),
+            throne: room.throne,
+            treasure: room.treasure as u32,
+            rotation: room.rotation as u32,
+            connections: vec![up.into(), right.into(), down.into(), left.into()],
+        }
+    }
+}
+
+impl From<&castle::Castle> for Castle {
+    fn from(castle: &castle::Castle) -> Self {
+        Castle {
+            rooms: castle
+                .rooms
+                .iter()
+                .map(|(pos, room)| PlacedRoom {
+                    pos: Some((*pos).into()),
+                    room: Some(room.into()),
+                })
+                .collect(),
+            damage: castle.damage as u32,
+            lost: castle.is_lost(),
+            treasure: castle.get_treasure() as u32,
+        }
+    }
+}
+
+impl From<&disaster::DamageCalculation> for DamageCalculation {
+    fn from(calculation: &disaster::DamageCalculation) -> Self {
+        DamageCalculation {
+            multiplier: calculation.multiplier as u32,
+            addition: calculation.addition as u32,
+            formula: calculation
+                .formula
+                .as_ref()
+                .map_or(String::new(), |f| f.to_string()),
+        }
+    }
+}
+
+impl From<&disaster::Disaster> for Disaster {
+    fn from(disaster: &disaster::Disaster) -> Self {
+        Disaster {
+            name: disaster.name.clone(),
+            diamond: Some((&disaster.diamond).into()),
+            cross: Some((&disaster.cross).into()),
+            moon: Some((&disaster.moon).into()),
+        }
+    }
+}
+
+impl From<&castle::Action> for Action {
+    fn from(action: &castle::Action) -> Self {
+        let kind = match action {
+            castle::Action::Place(index, pos) => action::Kind::Place(Place {
+                shop_index: *index as u32,
+                pos: Some((*pos).into()),
+            }),
+            castle::Action::Move(from, to) => action::Kind::Move(Move {
+                from: Some((*from).into()),
+                to: Some((*to).into()),
+            }),
+            castle::Action::Swap(pos1, pos2) => action::Kind::Swap(Swap {
+                pos1: Some((*pos1).into()),
+                pos2: Some((*pos2).into()),
+            }),
+            castle::Action::Discard(pos) => action::Kind::Discard(Discard {
+                pos: Some((*pos).into()),
+            }),
+        };
+        Action { kind: Some(kind) }
+    }
+}
+
+fn required(pos: Option<Pos>, field: &'static str) -> Result<castle::Pos, ProtoError> {
+    pos.map(castle::Pos::from)
+        .ok_or(ProtoError::MissingField(field))
+}
+
+impl TryFrom<Action> for castle::Action {
+    type Error = ProtoError;
+
+    fn try_from(action: Action) -> Result<Self, Self::Error> {
+        match action.kind.ok_or(ProtoError::MissingField("kind"))? {
+            action::Kind::Place(place) => Ok(castle::Action::Place(
+                place.shop_index as usize,
+                required(place.pos, "pos")?,
+            )),
+            action::Kind::Move(m) => Ok(castle::Action::Move(
+                required(m.from, "from")?,
+                required(m.to, "to")?,
+            )),
+            action::Kind::Swap(swap) => Ok(castle::Action::Swap(
+                required(swap.pos1, "pos1")?,
+                required(swap.pos2, "pos2")?,
+            )),
+            action::Kind::Discard(discard) => {
+                Ok(castle::Action::Discard(required(discard.pos, "pos")?))
+            }
+        }
+    }
+}
+
+fn seats(seats: &[usize]) -> Vec<u32> {
+    seats.iter().map(|&seat| seat as u32).collect()
+}
+
+impl From<&PlayerView> for GameView {
+    fn from(view: &PlayerView) -> Self {
+        GameView {
+            seat: view.seat.map(|seat| seat as u32),
+            castles: view.castles.iter().map(Castle::from).collect(),
+            turn_order: seats(&view.turn_order),
+            to_act: seats(&view.to_act),
+            shop: view.shop.iter().map(Room::from).collect(),
+            discard: view.discard.iter().map(Room::from).collect(),
+            previous_disasters: view.previous_disasters.iter().map(Disaster::from).collect(),
+            queued_disasters: view.queued_disasters.iter().map(Disaster::from).collect(),
+            deck_size: view.deck_size as u32,
+            round: view.round as u32,
+            action_seq: view.action_seq,
+            possible_actions: view.possible_actions.iter().map(Action::from).collect(),
+            is_over: view.is_over,
+        }
+    }
+}
+
+impl From<PlayerView> for GameView {
+    fn from(view: PlayerView) -> Self {
+        GameView::from(&view)
+    }
+}
+
+/// The spectator's view of the game, with no seat and no actions.
+impl From<&GameState> for GameView {
+    fn from(game: &GameState) -> Self {
+        game.player_view(&PlayerSecret::from("")).into()
+    }
+}
+
+impl From<GameState> for GameView {
+    fn from(game: GameState) -> Self {
+        GameView::from(&game)
+    }
+}
+
+impl From<&ErrorBody> for Error {
+    fn from(body: &ErrorBody) -> Self {
+        Error {
+            code: body.code.as_str().to_string(),
+            numeric_code: body.code.as_u16() as u32,
+            message: body.message.clone(),
+            details: body.details.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, GameView};
+    use crate::game::{test_game, PlayerSecret};
+    use disastle_castle_rust as castle;
+    use prost::Message;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn test_game_view_round_trip() {
+        let game = test_game(&["a", "b", "c"]);
+        let view = GameView::from(game.player_view(&PlayerSecret::from("a")));
+        assert_eq!(view.seat, Some(0));
+        assert_eq!(view.castles.len(), 3);
+        let decoded = GameView::decode(&view.encode_to_vec()[..]).unwrap();
+        assert_eq!(decoded, view);
+    }
+    #[test]
+    fn test_action_round_trip() {
+        for action in [
+            castle::Action::Place(3, (1, -1)),
+            castle::Action::Move((0, 1), (-2, 0)),
+            castle::Action::Swap((0, 1), (1, 0)),
+            castle::Action::Discard((0, -1)),
+        ]
+        .iter()
+        {
+            let message = Action::from(action);
+            let decoded = Action::decode(&message.encode_to_vec()[..]).unwrap();
+            assert_eq!(&castle::Action::try_from(decoded).unwrap(), action);
+        }
+        assert!(castle::Action::try_from(Action::default()).is_err());
+    }
+}
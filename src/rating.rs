@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const DEFAULT_RATING: f64 = 1500.0;
+/// Most a rating moves in one game.
+pub const K_FACTOR: f64 = 32.0;
+
+/// Elo rating of a player account across games.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Rating {
+    pub value: f64,
+    pub games: u32,
+}
+
+impl Default for Rating {
+    fn default() -> Rating {
+        Rating {
+            value: DEFAULT_RATING,
+            games: 0,
+        }
+    }
+}
+
+impl Rating {
+    /// Chance of beating a player rated `other`, counting a tie as half a win.
+    pub fn expected_score(&self, other: &Rating) -> f64 {
+        1.0 / (1.0 + 10f64.powf((other.value - self.value) / 400.0))
+    }
+}
+
+/// Update `ratings` from the standings of one finished game, such as
+/// `GameState::get_standings` mapped to accounts: places best first, with tied players
+/// sharing a place. Every player is scored against every other one as in a two-player game,
+/// with the K factor split between their opponents, so each game weighs the same however
+/// many played. Players without a rating start at `DEFAULT_RATING`.
+pub fn update_ratings<K: Ord + Clone>(ratings: &mut BTreeMap<K, Rating>, standings: &[Vec<K>]) {
+    let players: Vec<(usize, &K)> = standings
+        .iter()
+        .enumerate()
+        .flat_map(|(place, keys)| keys.iter().map(move |key| (place, key)))
+        .collect();
+    if players.len() < 2 {
+        return;
+    }
+    let before: Vec<Rating> = players
+        .iter()
+        .map(|(_, key)| ratings.get(key).copied().unwrap_or_default())
+        .collect();
+    let k = K_FACTOR / (players.len() - 1) as f64;
+    for (i, (place, key)) in players.iter().enumerate() {
+        let change: f64 = players
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(j, (other_place, _))| {
+                let score = match place.cmp(other_place) {
+                    std::cmp::Ordering::Less => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Greater => 0.0,
+                };
+                k * (score - before[i].expected_score(&before[j]))
+            })
+            .sum();
+        let rating = ratings.entry((*key).clone()).or_default();
+        rating.value = before[i].value + change;
+        rating.games += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{update_ratings, Rating, DEFAULT_RATING, K_FACTOR};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_update_ratings() {
+        let mut ratings: BTreeMap<&str, Rating> = BTreeMap::new();
+        update_ratings(&mut ratings, &[vec!["a"], vec!["b"]]);
+        assert_eq!(ratings["a"].value, DEFAULT_RATING + K_FACTOR / 2.0);
+        assert_eq!(ratings["b"].value, DEFAULT_RATING - K_FACTOR / 2.0);
+
+        update_ratings(&mut ratings, &[vec!["c"], vec!["a", "b"], vec!["d"]]);
+        let total: f64 = ratings.values().map(|r| r.value).sum();
+        assert!((total - 4.0 * DEFAULT_RATING).abs() < 1e-9);
+        assert!(ratings["c"].value > DEFAULT_RATING && ratings["d"].value < DEFAULT_RATING);
+        assert_eq!(ratings["a"].games, 2);
+    }
+}
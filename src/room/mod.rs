@@ -0,0 +1,212 @@
+mod registry;
+mod validate;
+
+use crate::castle::connections;
+use disastle_castle_rust::Room;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+pub use registry::{RegistryError, RoomRef, RoomRegistry};
+pub use validate::{validate_cards, CardError, CardErrors};
+
+/// Rotation helpers on `disastle_castle_rust::Room`, whose own `rotate_right`/`rotate_left`
+/// consume the room and return nothing.
+pub trait RoomExt {
+    /// Copy of the room turned a quarter turn clockwise: the up connection faces right.
+    fn rotated_right(&self) -> Room;
+    /// Copy of the room turned a quarter turn counterclockwise.
+    fn rotated_left(&self) -> Room;
+    /// Copy of the room turned clockwise `turns` quarter turns. `rotation` records the total
+    /// turns modulo 4, so art can be oriented to match.
+    fn rotated(&self, turns: u8) -> Room;
+}
+
+impl RoomExt for Room {
+    fn rotated_right(&self) -> Room {
+        self.rotated(1)
+    }
+    fn rotated_left(&self) -> Room {
+        self.rotated(3)
+    }
+    fn rotated(&self, turns: u8) -> Room {
+        let mut rotated = self.clone();
+        let mut connections = connections(self);
+        connections.rotate_right((turns % 4) as usize);
+        let [up, right, down, left] = connections;
+        rotated.connections = (up, right, down, left);
+        rotated.rotation = (self.rotation % 4 + turns % 4) % 4;
+        rotated
+    }
+}
+
+/// Quarter turns taking `base` to `room`, if `room` is a rotation of `base`.
+pub(crate) fn rotation_from(base: &Room, room: &Room) -> Option<u8> {
+    let turns = (room.rotation % 4 + 4 - base.rotation % 4) % 4;
+    if &base.rotated(turns) == room {
+        Some(turns)
+    } else {
+        None
+    }
+}
+
+/// What a room does beyond its connections. `GameState` only applies `Shield`; the others
+/// are presentation only for now, for clients to show on the card.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Ability {
+    /// Treasure on top of the room's own, counted by `RoomCard::treasure` but not scored.
+    Treasure(u8),
+    /// Disaster damage of any kind absorbed each time a disaster strikes.
+    Shield(u8),
+    /// Extra rooms drawn into the shop. Not applied by game logic.
+    Draw(u8),
+    /// Extra swaps allowed per turn. Not applied by game logic.
+    Swap(u8),
+}
+
+/// A room as described in card data: the rules-level `Room` plus typed metadata that
+/// `disastle_castle_rust` does not know about.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct RoomCard {
+    /// Identifier unique within every pack loaded together, stable across releases.
+    pub id: String,
+    pub room: Room,
+    #[serde(default)]
+    pub abilities: Vec<Ability>,
+    /// Free-form categories such as `"kitchen"` or `"outdoor"`, for set-collection scoring
+    /// and disasters targeting a category.
+    #[serde(default)]
+    pub tags: BTreeSet<String>,
+    /// Number of copies shuffled into the deck.
+    #[serde(default = "one")]
+    pub copies: u8,
+    /// Presentation only, never read by game logic.
+    #[serde(default)]
+    pub art: Option<String>,
+    #[serde(default)]
+    pub flavor_text: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+fn one() -> u8 {
+    1
+}
+
+impl RoomCard {
+    pub fn new(id: impl Into<String>, room: Room) -> RoomCard {
+        RoomCard {
+            id: id.into(),
+            room,
+            abilities: Vec::new(),
+            tags: BTreeSet::new(),
+            copies: 1,
+            art: None,
+            flavor_text: None,
+            color: None,
+        }
+    }
+    /// Whether `room` is this card's room, in any rotation.
+    pub fn matches(&self, room: &Room) -> bool {
+        rotation_from(&self.room, room).is_some()
+    }
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.contains(tag)
+    }
+    fn total(&self, f: impl Fn(&Ability) -> Option<u8>) -> u8 {
+        self.abilities
+            .iter()
+            .filter_map(f)
+            .fold(0, u8::saturating_add)
+    }
+    /// The room's own treasure plus any treasure abilities.
+    pub fn treasure(&self) -> u8 {
+        self.room.treasure.saturating_add(self.total(|a| match a {
+            Ability::Treasure(n) => Some(*n),
+            _ => None,
+        }))
+    }
+    pub fn shield(&self) -> u8 {
+        self.total(|a| match a {
+            Ability::Shield(n) => Some(*n),
+            _ => None,
+        })
+    }
+    pub fn draw(&self) -> u8 {
+        self.total(|a| match a {
+            Ability::Draw(n) => Some(*n),
+            _ => None,
+        })
+    }
+    pub fn swaps(&self) -> u8 {
+        self.total(|a| match a {
+            Ability::Swap(n) => Some(*n),
+            _ => None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Ability, RoomCard, RoomExt};
+    use disastle_castle_rust::Connection;
+
+    const ROOM: &str = r#"Room(
+        throne: false,
+        treasure: 1,
+        name: "Armory",
+        rotation: 0,
+        connections: (None, Cross(true), None, Diamond(false))
+    )"#;
+
+    #[test]
+    fn test_deserialize_room_card() {
+        let card: RoomCard = ron::from_str(&format!(
+            "RoomCard(id: \"armory\", room: {}, abilities: [Treasure(2), Shield(1), Shield(2)], tags: [\"armory\"])",
+            ROOM
+        ))
+        .unwrap();
+        assert_eq!(card.treasure(), 3);
+        assert_eq!(card.shield(), 3);
+        assert_eq!(card.draw(), 0);
+        assert!(card.has_tag("armory"));
+        assert_eq!(card.art, None);
+        let text = ron::to_string(&RoomCard {
+            art: Some("armory.png".to_string()),
+            color: Some("#8a3bd8".to_string()),
+            ..card.clone()
+        })
+        .unwrap();
+        let styled: RoomCard = ron::from_str(&text).unwrap();
+        assert_eq!(styled.color.as_deref(), Some("#8a3bd8"));
+        assert_eq!(styled.treasure(), card.treasure());
+        assert!(card.matches(&card.room.rotated_left()));
+
+        let plain: RoomCard =
+            ron::from_str(&format!("RoomCard(id: \"armory\", room: {})", ROOM)).unwrap();
+        assert_eq!(plain, RoomCard::new("armory", card.room.clone()));
+        assert_eq!(
+            card.abilities,
+            vec![Ability::Treasure(2), Ability::Shield(1), Ability::Shield(2)]
+        );
+    }
+
+    #[test]
+    fn test_rotation_round_trip() {
+        let card: RoomCard =
+            ron::from_str(&format!("RoomCard(id: \"armory\", room: {})", ROOM)).unwrap();
+        let room = card.room;
+        let right = room.rotated_right();
+        assert_eq!(right.connections.1, Connection::None);
+        assert_eq!(right.connections.2, Connection::Cross(true));
+        assert_eq!(right.connections.0, Connection::Diamond(false));
+        assert_eq!(right.rotation, 1);
+        assert_eq!(right.rotated_left(), room);
+        assert_eq!(room.rotated_left().rotation, 3);
+        assert_eq!(room.rotated(4), room);
+        assert_eq!(
+            room.rotated_right().rotated_right().rotated_right(),
+            room.rotated_left()
+        );
+    }
+}
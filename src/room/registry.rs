@@ -0,0 +1,168 @@
+use super::{rotation_from, RoomCard, RoomExt};
+use disastle_castle_rust::Room;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RegistryError {
+    #[error("Room id {0} is used by more than one card")]
+    DuplicateId(String),
+}
+
+/// A room stored as the id of its card and the quarter turns it was rotated by, resolved
+/// through a `RoomRegistry`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct RoomRef {
+    pub id: String,
+    #[serde(default)]
+    pub rotation: u8,
+}
+
+/// Room cards indexed by id, iterated in the order they were loaded.
+///
+/// `GameState` still holds full `Room` values; `room_ref` and `resolve` convert them to and
+/// from `RoomRef` for callers that store or send rooms by id.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RoomRegistry {
+    cards: Vec<RoomCard>,
+    by_id: BTreeMap<String, usize>,
+}
+
+impl RoomRegistry {
+    pub fn new(cards: Vec<RoomCard>) -> Result<RoomRegistry, RegistryError> {
+        let mut registry = RoomRegistry::default();
+        registry.extend(cards)?;
+        Ok(registry)
+    }
+    /// Add cards from another pack, rejecting ids that are already registered. Nothing is
+    /// added if any id is rejected.
+    pub fn extend(&mut self, cards: Vec<RoomCard>) -> Result<(), RegistryError> {
+        let mut ids = BTreeSet::new();
+        for card in &cards {
+            if self.by_id.contains_key(&card.id) || !ids.insert(card.id.as_str()) {
+                return Err(RegistryError::DuplicateId(card.id.clone()));
+            }
+        }
+        for card in cards {
+            self.by_id.insert(card.id.clone(), self.cards.len());
+            self.cards.push(card);
+        }
+        Ok(())
+    }
+    pub fn get_by_id(&self, id: &str) -> Option<&RoomCard> {
+        self.by_id.get(id).map(|&i| &self.cards[i])
+    }
+    /// Every card with this display name; names are not unique.
+    pub fn get_by_name(&self, name: &str) -> Vec<&RoomCard> {
+        self.cards.iter().filter(|c| c.room.name == name).collect()
+    }
+    /// The card `room` was dealt from, in any rotation.
+    pub fn find(&self, room: &Room) -> Option<&RoomCard> {
+        self.cards.iter().find(|c| c.matches(room))
+    }
+    pub fn id_of(&self, room: &Room) -> Option<&str> {
+        self.find(room).map(|c| c.id.as_str())
+    }
+    /// Reference to `room` by card id, or `None` if no registered card deals it.
+    pub fn room_ref(&self, room: &Room) -> Option<RoomRef> {
+        self.cards.iter().find_map(|card| {
+            rotation_from(&card.room, room).map(|rotation| RoomRef {
+                id: card.id.clone(),
+                rotation,
+            })
+        })
+    }
+    /// The room `room_ref` points to, or `None` if its id is not registered.
+    pub fn resolve(&self, room_ref: &RoomRef) -> Option<Room> {
+        self.get_by_id(&room_ref.id)
+            .map(|card| card.room.rotated(room_ref.rotation))
+    }
+    pub fn cards(&self) -> &[RoomCard] {
+        &self.cards
+    }
+    pub fn iter(&self) -> std::slice::Iter<'_, RoomCard> {
+        self.cards.iter()
+    }
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a RoomRegistry {
+    type Item = &'a RoomCard;
+    type IntoIter = std::slice::Iter<'a, RoomCard>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{load_rooms_from_str, room::RoomExt};
+
+    fn cards() -> Vec<RoomCard> {
+        load_rooms_from_str(include_str!("../../rooms.ron"))
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, room)| RoomCard::new(format!("base-{}", i), room))
+            .collect()
+    }
+
+    #[test]
+    fn test_registry_lookup() {
+        let registry = RoomRegistry::new(cards()).unwrap();
+        assert_eq!(registry.len(), 100);
+        let card = registry.get_by_id("base-7").unwrap();
+        assert!(registry
+            .get_by_name(&card.room.name)
+            .iter()
+            .any(|c| c.id == "base-7"));
+        assert!(registry.find(&card.room.rotated_right()).is_some());
+        assert_eq!(
+            registry.iter().map(|c| c.id.clone()).collect::<Vec<_>>(),
+            (0..100).map(|i| format!("base-{}", i)).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_room_refs() {
+        let registry = RoomRegistry::new(cards()).unwrap();
+        for card in &registry {
+            let room = card.room.rotated(3);
+            let room_ref = registry.room_ref(&room).unwrap();
+            assert_eq!(registry.resolve(&room_ref), Some(room));
+        }
+        let missing = RoomRef {
+            id: "exp-0".to_string(),
+            rotation: 0,
+        };
+        assert_eq!(registry.resolve(&missing), None);
+    }
+
+    #[test]
+    fn test_registry_rejects_duplicate_ids() {
+        let mut registry = RoomRegistry::new(cards()).unwrap();
+        assert_eq!(
+            registry.extend(cards()),
+            Err(RegistryError::DuplicateId("base-0".to_string()))
+        );
+        // A rejected pack leaves the registry as it was
+        let mut expansion = cards();
+        expansion
+            .iter_mut()
+            .for_each(|c| c.id = c.id.replace("base", "exp"));
+        expansion.push(expansion[0].clone());
+        let before = registry.clone();
+        assert_eq!(
+            registry.extend(expansion),
+            Err(RegistryError::DuplicateId("exp-0".to_string()))
+        );
+        assert_eq!(registry, before);
+    }
+}
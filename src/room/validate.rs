@@ -0,0 +1,121 @@
+use super::RoomCard;
+use disastle_castle_rust::Connection;
+use std::{collections::BTreeSet, fmt};
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum CardError {
+    #[error("Room card at index {index} has an empty id")]
+    EmptyId { index: usize },
+    #[error("Room {id} has an empty name")]
+    EmptyName { id: String },
+    #[error("Room {id} has no connections")]
+    NoConnections { id: String },
+    #[error("Room id {id} is used by more than one card")]
+    DuplicateId { id: String },
+}
+
+/// Every problem found in a set of cards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardErrors(pub Vec<CardError>);
+
+impl fmt::Display for CardErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self.0.iter().map(|e| e.to_string()).collect();
+        write!(f, "{}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for CardErrors {}
+
+impl RoomCard {
+    /// Check the card on its own: a non-empty id and name and at least one connection.
+    /// Gold flags need no check since only typed connections can carry one.
+    pub fn validate(&self, index: usize) -> Result<(), Vec<CardError>> {
+        let mut errors = Vec::new();
+        if self.id.is_empty() {
+            errors.push(CardError::EmptyId { index });
+        }
+        if self.room.name.trim().is_empty() {
+            errors.push(CardError::EmptyName {
+                id: self.id.clone(),
+            });
+        }
+        if super::connections(&self.room)
+            .iter()
+            .all(|c| *c == Connection::None)
+        {
+            errors.push(CardError::NoConnections {
+                id: self.id.clone(),
+            });
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Validate every card of a pack and check that ids are unique within it.
+pub fn validate_cards(cards: &[RoomCard]) -> Result<(), CardErrors> {
+    let mut errors = Vec::new();
+    let mut ids = BTreeSet::new();
+    for (index, card) in cards.iter().enumerate() {
+        if let Err(mut card_errors) = card.validate(index) {
+            errors.append(&mut card_errors);
+        }
+        if !card.id.is_empty() && !ids.insert(card.id.as_str()) {
+            errors.push(CardError::DuplicateId {
+                id: card.id.clone(),
+            });
+        }
+    }
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(CardErrors(errors))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::load_rooms_from_str;
+
+    #[test]
+    fn test_validate_cards() {
+        let mut cards: Vec<RoomCard> = load_rooms_from_str(include_str!("../../rooms.ron"))
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(i, room)| RoomCard::new(format!("base-{}", i), room))
+            .collect();
+        assert_eq!(validate_cards(&cards), Ok(()));
+
+        cards[1].id = "base-0".to_string();
+        cards[2].room.name = String::new();
+        cards[3].room.connections = (
+            Connection::None,
+            Connection::None,
+            Connection::None,
+            Connection::None,
+        );
+        cards[4].id = String::new();
+        assert_eq!(
+            validate_cards(&cards),
+            Err(CardErrors(vec![
+                CardError::DuplicateId {
+                    id: "base-0".to_string()
+                },
+                CardError::EmptyName {
+                    id: "base-2".to_string()
+                },
+                CardError::NoConnections {
+                    id: "base-3".to_string()
+                },
+                CardError::EmptyId { index: 4 },
+            ]))
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use disastle_castle_rust::Room;
+#[cfg(feature = "fs")]
+use std::{fs, io, path::Path};
+
+use crate::{pack::CardPack, room::RoomCard};
+
+// Canonical output: cards in a stable order, fields in declaration order and the same
+// pretty layout every time, so generated or merged card files diff cleanly.
+
+fn pretty<T: serde::Serialize>(value: &T) -> ron::Result<String> {
+    let mut content = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::new())?;
+    content.push('\n');
+    Ok(content)
+}
+
+fn sort_by_name(rooms: &mut [Room]) {
+    rooms.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.cmp(b)));
+}
+
+/// Rooms sorted by name, then by their other fields.
+pub fn rooms_to_string(rooms: &[Room]) -> ron::Result<String> {
+    let mut rooms = rooms.to_vec();
+    sort_by_name(&mut rooms);
+    pretty(&rooms)
+}
+
+/// Room cards sorted by id.
+pub fn room_cards_to_string(cards: &[RoomCard]) -> ron::Result<String> {
+    let mut cards = cards.to_vec();
+    cards.sort_by(|a, b| a.id.cmp(&b.id));
+    pretty(&cards)
+}
+
+/// Pack with its thrones and disasters sorted by name and its rooms by id.
+pub fn pack_to_string(pack: &CardPack) -> ron::Result<String> {
+    let mut pack = pack.clone();
+    sort_by_name(&mut pack.thrones);
+    pack.rooms.sort_by(|a, b| a.id.cmp(&b.id));
+    pack.disasters.sort();
+    pretty(&pack)
+}
+
+#[cfg(feature = "fs")]
+fn invalid_data(e: ron::Error) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+#[cfg(feature = "fs")]
+pub fn save_rooms(path: &Path, rooms: &[Room]) -> io::Result<()> {
+    fs::write(path, rooms_to_string(rooms).map_err(invalid_data)?)
+}
+
+#[cfg(feature = "fs")]
+pub fn save_room_cards(path: &Path, cards: &[RoomCard]) -> io::Result<()> {
+    fs::write(path, room_cards_to_string(cards).map_err(invalid_data)?)
+}
+
+#[cfg(feature = "fs")]
+pub fn save_pack(path: &Path, pack: &CardPack) -> io::Result<()> {
+    fs::write(path, pack_to_string(pack).map_err(invalid_data)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rooms_to_string, sort_by_name};
+    use crate::load_rooms_from_str;
+
+    #[test]
+    fn test_rooms_are_canonical() {
+        let mut rooms = load_rooms_from_str(include_str!("../rooms.ron")).unwrap();
+        let content = rooms_to_string(&rooms).unwrap();
+        rooms.reverse();
+        assert_eq!(rooms_to_string(&rooms).unwrap(), content);
+        let loaded = load_rooms_from_str(&content).unwrap();
+        assert!(loaded.windows(2).all(|w| w[0].name <= w[1].name));
+        sort_by_name(&mut rooms);
+        assert_eq!(loaded, rooms);
+    }
+}
@@ -0,0 +1,99 @@
+//! JSON Schema export of the public DTOs, for client authors generating bindings.
+//!
+//! Types owned by `disastle_castle_rust` can't derive `JsonSchema` here, so their serde
+//! shape is mirrored by the `*Schema` types below.
+
+use schemars::{schema::RootSchema, schema_for, JsonSchema};
+use std::collections::BTreeMap;
+#[cfg(feature = "fs")]
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    result,
+};
+
+use crate::{
+    disaster::Disaster,
+    error::ErrorBody,
+    game::{GameSummary, PlayerView},
+};
+
+#[derive(JsonSchema)]
+#[schemars(rename = "Connection")]
+pub enum ConnectionSchema {
+    None,
+    Diamond(bool),
+    Cross(bool),
+    Moon(bool),
+    Wild,
+}
+
+/// Connections are listed up, right, down, left.
+#[derive(JsonSchema)]
+#[schemars(rename = "Room")]
+pub struct RoomSchema {
+    pub throne: bool,
+    pub treasure: u8,
+    pub name: String,
+    pub rotation: u8,
+    pub connections: (
+        ConnectionSchema,
+        ConnectionSchema,
+        ConnectionSchema,
+        ConnectionSchema,
+    ),
+}
+
+/// Positions are `(x, y)` pairs; `Place` takes the shop index first.
+#[derive(JsonSchema)]
+#[schemars(rename = "Action")]
+pub enum ActionSchema {
+    Place(usize, (i32, i32)),
+    Move((i32, i32), (i32, i32)),
+    Swap((i32, i32), (i32, i32)),
+    Discard((i32, i32)),
+}
+
+/// Schemas of every public DTO, keyed by the file stem they are written under.
+pub fn schemas() -> BTreeMap<&'static str, RootSchema> {
+    let mut schemas = BTreeMap::new();
+    schemas.insert("player_view", schema_for!(PlayerView));
+    schemas.insert("action", schema_for!(ActionSchema));
+    schemas.insert("room", schema_for!(RoomSchema));
+    schemas.insert("disaster", schema_for!(Disaster));
+    schemas.insert("error", schema_for!(ErrorBody));
+    schemas.insert("game_summary", schema_for!(GameSummary));
+    schemas
+}
+
+/// Write each schema to `dir/<name>.schema.json`.
+#[cfg(feature = "fs")]
+pub fn write_schemas(dir: &Path) -> result::Result<(), io::Error> {
+    for (name, schema) in schemas() {
+        let content = serde_json::to_string_pretty(&schema)?;
+        let mut file = File::create(dir.join(format!("{}.schema.json", name)))?;
+        file.write_all(content.as_bytes())?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "fs"))]
+mod tests {
+    use super::{schemas, write_schemas};
+    use std::{env, fs, process};
+
+    #[test]
+    fn test_write_schemas() {
+        let dir = env::temp_dir().join(format!("disastle_schemas_{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        write_schemas(&dir).unwrap();
+        for name in schemas().keys() {
+            let path = dir.join(format!("{}.schema.json", name));
+            let content = fs::read_to_string(&path).unwrap();
+            let schema: serde_json::Value = serde_json::from_str(&content).unwrap();
+            assert!(schema.get("$schema").is_some());
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::game::GameSummary;
+
+/// Lifetime statistics of one player account, built from the summaries of their games.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct PlayerStats {
+    pub games: u32,
+    pub wins: u32,
+    pub resignations: u32,
+    pub total_treasure: u32,
+    /// How many times each room ended up in the player's castle, by name.
+    pub room_picks: BTreeMap<String, u32>,
+}
+
+impl PlayerStats {
+    /// Count the game summarised by `summary`, played from `seat`. Aborted games and seats
+    /// not in the game are ignored.
+    pub fn record(&mut self, summary: &GameSummary, seat: usize) {
+        if summary.aborted || seat >= summary.treasure.len() {
+            return;
+        }
+        self.games += 1;
+        if summary.winners.contains(&seat) {
+            self.wins += 1;
+        }
+        if summary.resigned.contains(&seat) {
+            self.resignations += 1;
+        }
+        self.total_treasure += summary.treasure[seat] as u32;
+        for room in summary.rooms.get(seat).into_iter().flatten() {
+            *self.room_picks.entry(room.clone()).or_insert(0) += 1;
+        }
+    }
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.wins as f64 / self.games as f64
+    }
+    pub fn average_treasure(&self) -> f64 {
+        if self.games == 0 {
+            return 0.0;
+        }
+        self.total_treasure as f64 / self.games as f64
+    }
+    /// The `count` rooms picked most often, most picked first and by name on ties.
+    pub fn favorite_rooms(&self, count: usize) -> Vec<(&str, u32)> {
+        let mut rooms: Vec<(&str, u32)> = self
+            .room_picks
+            .iter()
+            .map(|(name, picks)| (name.as_str(), *picks))
+            .collect();
+        rooms.sort_by(|(_, a), (_, b)| b.cmp(a));
+        rooms.truncate(count);
+        rooms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PlayerStats;
+    use crate::game::GameSummary;
+
+    #[test]
+    fn test_record_games() {
+        let mut summary = GameSummary {
+            rounds: 6,
+            standings: vec![vec![1], vec![0]],
+            winners: vec![1],
+            treasure: vec![3, 7],
+            rooms: vec![
+                vec!["Armory".to_string(), "Vault".to_string()],
+                vec!["Vault".to_string()],
+            ],
+            disasters: Vec::new(),
+            resigned: Vec::new(),
+            aborted: false,
+        };
+        let mut stats = PlayerStats::default();
+        stats.record(&summary, 1);
+        stats.record(&summary, 0);
+        summary.aborted = true;
+        stats.record(&summary, 1);
+        assert_eq!(stats.games, 2);
+        assert_eq!(stats.win_rate(), 0.5);
+        assert_eq!(stats.average_treasure(), 5.0);
+        assert_eq!(stats.favorite_rooms(1), vec![("Vault", 2)]);
+    }
+}
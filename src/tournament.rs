@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::{cmp::Ordering, collections::BTreeMap};
+use thiserror::Error;
+
+#[derive(Clone, Debug, PartialEq, Eq, Error)]
+pub enum TournamentError {
+    #[error("A tournament needs at least two players")]
+    NotEnoughPlayers,
+    #[error("Tables must seat at least two players")]
+    TableTooSmall,
+    #[error("The current round still has games without a result")]
+    RoundInProgress,
+    #[error("There is no table {table} in the current round")]
+    UnknownTable { table: usize },
+    #[error("The result for table {table} does not list exactly its players")]
+    InvalidResult { table: usize },
+}
+
+/// One game of a round and, once it is over, its standings: places best first, with tied
+/// players sharing a place.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Table<K> {
+    pub players: Vec<K>,
+    pub result: Option<Vec<Vec<K>>>,
+}
+
+/// Swiss tournament over multiplayer games. Each round seats players with similar scores
+/// together, avoiding earlier tablemates where it can. A game scores a point for each
+/// opponent placed lower and half a point for each opponent tied with.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tournament<K> {
+    players: Vec<K>,
+    table_size: usize,
+    rounds: Vec<Vec<Table<K>>>,
+}
+
+impl<K: Ord + Clone> Tournament<K> {
+    /// Players are listed by seed, the first one seated first in the opening round.
+    pub fn new(players: Vec<K>, table_size: usize) -> Result<Tournament<K>, TournamentError> {
+        if table_size < 2 {
+            return Err(TournamentError::TableTooSmall);
+        } else if players.len() < 2 {
+            return Err(TournamentError::NotEnoughPlayers);
+        }
+        Ok(Tournament {
+            players,
+            table_size,
+            rounds: Vec::new(),
+        })
+    }
+    pub fn get_players(&self) -> &[K] {
+        &self.players
+    }
+    /// Number of rounds paired so far.
+    pub fn get_round(&self) -> usize {
+        self.rounds.len()
+    }
+    /// Tables of the current round, empty before the first one is paired.
+    pub fn get_tables(&self) -> &[Table<K>] {
+        self.rounds.last().map(Vec::as_slice).unwrap_or(&[])
+    }
+    pub fn is_round_complete(&self) -> bool {
+        self.get_tables().iter().all(|t| t.result.is_some())
+    }
+    /// Seat the next round. Tables are as even as possible, with the larger ones first, and
+    /// never exceed the table size unless that would leave a player alone: then there is
+    /// one table fewer, and the players who would have sat there take extra seats at the
+    /// first tables.
+    pub fn pair_round(&mut self) -> Result<&[Table<K>], TournamentError> {
+        if !self.is_round_complete() {
+            return Err(TournamentError::RoundInProgress);
+        }
+        let scores = self.scores();
+        let mut remaining = self.players.clone();
+        // Stable, so equal scores keep seed order
+        remaining.sort_by(|a, b| scores[b].partial_cmp(&scores[a]).unwrap_or(Ordering::Equal));
+        let n = remaining.len();
+        let mut num_tables = n.div_ceil(self.table_size);
+        while num_tables > 1 && n / num_tables < 2 {
+            num_tables -= 1;
+        }
+        let mut tables = Vec::new();
+        for i in 0..num_tables {
+            let size = n / num_tables + usize::from(i < n % num_tables);
+            let mut players = vec![remaining.remove(0)];
+            while players.len() < size {
+                let next = remaining
+                    .iter()
+                    .position(|p| players.iter().all(|q| !self.have_met(p, q)))
+                    .unwrap_or(0);
+                players.push(remaining.remove(next));
+            }
+            tables.push(Table {
+                players,
+                result: None,
+            });
+        }
+        self.rounds.push(tables);
+        Ok(self.get_tables())
+    }
+    /// Record the standings of `table` in the current round.
+    pub fn record(&mut self, table: usize, standings: Vec<Vec<K>>) -> Result<(), TournamentError> {
+        let entry = self
+            .rounds
+            .last_mut()
+            .and_then(|tables| tables.get_mut(table))
+            .ok_or(TournamentError::UnknownTable { table })?;
+        let mut listed: Vec<&K> = standings.iter().flatten().collect();
+        let mut players: Vec<&K> = entry.players.iter().collect();
+        listed.sort();
+        players.sort();
+        if listed != players {
+            return Err(TournamentError::InvalidResult { table });
+        }
+        entry.result = Some(standings);
+        Ok(())
+    }
+    /// Every player with their score, best first.
+    pub fn get_standings(&self) -> Vec<(K, f64)> {
+        let scores = self.scores();
+        let mut standings: Vec<(K, f64)> = self
+            .players
+            .iter()
+            .map(|p| (p.clone(), scores[p]))
+            .collect();
+        standings.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+        standings
+    }
+    fn scores(&self) -> BTreeMap<&K, f64> {
+        let mut scores: BTreeMap<&K, f64> = self.players.iter().map(|p| (p, 0.0)).collect();
+        let results = self
+            .rounds
+            .iter()
+            .flatten()
+            .filter_map(|t| t.result.as_ref());
+        for result in results {
+            for (place, keys) in result.iter().enumerate() {
+                let below: usize = result[place + 1..].iter().map(Vec::len).sum();
+                let score = below as f64 + (keys.len() - 1) as f64 / 2.0;
+                for key in keys {
+                    if let Some(total) = scores.get_mut(key) {
+                        *total += score;
+                    }
+                }
+            }
+        }
+        scores
+    }
+    fn have_met(&self, a: &K, b: &K) -> bool {
+        self.rounds
+            .iter()
+            .flatten()
+            .any(|t| t.players.contains(a) && t.players.contains(b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tournament, TournamentError};
+
+    #[test]
+    fn test_swiss_rounds() {
+        let mut tournament = Tournament::new(vec!["a", "b", "c", "d", "e"], 2).unwrap();
+        let tables = tournament.pair_round().unwrap();
+        assert_eq!(tables[0].players, vec!["a", "b", "c"]);
+        assert_eq!(tables[1].players, vec!["d", "e"]);
+        assert_eq!(
+            tournament.pair_round(),
+            Err(TournamentError::RoundInProgress)
+        );
+        assert_eq!(
+            tournament.record(0, vec![vec!["a"], vec!["c"]]),
+            Err(TournamentError::InvalidResult { table: 0 })
+        );
+        tournament
+            .record(0, vec![vec!["b"], vec!["a"], vec!["c"]])
+            .unwrap();
+        tournament.record(1, vec![vec!["d", "e"]]).unwrap();
+        assert_eq!(
+            tournament.get_standings(),
+            vec![("b", 2.0), ("a", 1.0), ("d", 0.5), ("e", 0.5), ("c", 0.0)]
+        );
+
+        let tables = tournament.pair_round().unwrap();
+        assert_eq!(tables[0].players, vec!["b", "d", "a"]);
+        assert_eq!(tables[1].players, vec!["e", "c"]);
+    }
+}
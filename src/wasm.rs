@@ -0,0 +1,158 @@
+//! wasm-bindgen bindings so the rules engine can run in the browser.
+//!
+//! Values cross the boundary as JSON strings in the same shape the serde derives produce,
+//! and errors are thrown as JSON `ErrorBody` strings. Each binding wraps a `try_` method
+//! reporting the `ErrorBody` itself, so the JSON handling can be tested off the browser.
+
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    error::{ErrorBody, ErrorCode},
+    game::{migrate, GameError, GameSetting, GameState, PlayerSecret},
+};
+use disastle_castle_rust::Action;
+
+fn to_js_error(body: ErrorBody) -> JsValue {
+    JsValue::from_str(&serde_json::to_string(&body).unwrap_or(body.message))
+}
+
+fn to_json<T: Serialize>(value: &T) -> Result<String, ErrorBody> {
+    serde_json::to_string(value).map_err(|e| ErrorBody::new(ErrorCode::Internal, e.to_string()))
+}
+
+fn from_json<T: serde::de::DeserializeOwned>(json: &str) -> Result<T, ErrorBody> {
+    serde_json::from_str(json).map_err(|e| ErrorBody::new(ErrorCode::MalformedInput, e.to_string()))
+}
+
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: GameState,
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    /// `players` is a JSON array of player secrets, `setting` a JSON `GameSetting`. Throws
+    /// an `invalid_setting` error if the setting cannot deal a game for them.
+    #[wasm_bindgen(constructor)]
+    pub fn new(players: &str, setting: &str) -> Result<WasmGame, JsValue> {
+        WasmGame::try_new(players, setting).map_err(to_js_error)
+    }
+    /// Load a game serialized by `toJson` or by any earlier schema version.
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(state: &str) -> Result<WasmGame, JsValue> {
+        WasmGame::try_from_json(state).map_err(to_js_error)
+    }
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsValue> {
+        to_json(&self.game.to_versioned()).map_err(to_js_error)
+    }
+    #[wasm_bindgen(js_name = playerView)]
+    pub fn player_view(&self, secret: &str) -> Result<String, JsValue> {
+        to_json(&self.game.player_view(&PlayerSecret::from(secret))).map_err(to_js_error)
+    }
+    #[wasm_bindgen(js_name = possibleActions)]
+    pub fn possible_actions(&self, secret: &str) -> Result<String, JsValue> {
+        to_json(&self.game.possible_actions(&PlayerSecret::from(secret))).map_err(to_js_error)
+    }
+    /// Apply a JSON `Action`, returning the resulting game and leaving this one untouched.
+    pub fn action(
+        &self,
+        secret: &str,
+        expected_seq: u64,
+        action: &str,
+    ) -> Result<WasmGame, JsValue> {
+        self.try_action(secret, expected_seq, action)
+            .map_err(to_js_error)
+    }
+    /// JSON `ViewDelta` of the player's view the action would produce, to show a move before
+    /// the server confirms it.
+    pub fn predict(
+        &self,
+        secret: &str,
+        expected_seq: u64,
+        action: &str,
+    ) -> Result<String, JsValue> {
+        self.try_predict(secret, expected_seq, action)
+            .map_err(to_js_error)
+    }
+    #[wasm_bindgen(js_name = actionSeq)]
+    pub fn action_seq(&self) -> u64 {
+        self.game.get_action_seq()
+    }
+    #[wasm_bindgen(js_name = isOver)]
+    pub fn is_over(&self) -> bool {
+        self.game.is_over()
+    }
+    #[wasm_bindgen(js_name = isVictorious)]
+    pub fn is_victorious(&self, secret: &str) -> bool {
+        self.game.is_victorious(&PlayerSecret::from(secret))
+    }
+}
+
+impl WasmGame {
+    fn try_new(players: &str, setting: &str) -> Result<WasmGame, ErrorBody> {
+        let players: Vec<PlayerSecret> = from_json(players)?;
+        let setting: GameSetting = from_json(setting)?;
+        let game = GameState::new(players, setting).map_err(|e| GameError::from(e).to_body())?;
+        Ok(WasmGame { game })
+    }
+    fn try_from_json(state: &str) -> Result<WasmGame, ErrorBody> {
+        let game = migrate(from_json(state)?).map_err(|e| e.to_body())?;
+        Ok(WasmGame { game })
+    }
+    fn try_action(
+        &self,
+        secret: &str,
+        expected_seq: u64,
+        action: &str,
+    ) -> Result<WasmGame, ErrorBody> {
+        let action: Action = from_json(action)?;
+        let game = self
+            .game
+            .action(&PlayerSecret::from(secret), expected_seq, action)
+            .map_err(|e| e.to_body())?;
+        Ok(WasmGame { game })
+    }
+    fn try_predict(
+        &self,
+        secret: &str,
+        expected_seq: u64,
+        action: &str,
+    ) -> Result<String, ErrorBody> {
+        let action: Action = from_json(action)?;
+        let delta = self
+            .game
+            .predict(&PlayerSecret::from(secret), expected_seq, action)
+            .map_err(|e| e.to_body())?;
+        to_json(&delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::WasmGame;
+    use crate::{
+        error::ErrorCode,
+        game::{test_game, PlayerView},
+    };
+
+    #[test]
+    fn test_json_in_and_out() {
+        let mut setting = test_game(&["a"]).setting;
+        let players = r#"["a","b"]"#;
+        let game = WasmGame::try_new(players, &serde_json::to_string(&setting).unwrap()).unwrap();
+        let view: PlayerView = serde_json::from_str(&game.player_view("a").unwrap()).unwrap();
+        assert_eq!(view.seat, Some(0));
+        let loaded = WasmGame::try_from_json(&game.to_json().unwrap()).unwrap();
+        assert_eq!(loaded.game, game.game);
+
+        let error = game.try_action("a", 0, "{}").err().unwrap();
+        assert_eq!(error.code, ErrorCode::MalformedInput);
+        setting.num_shop = 0;
+        let error = WasmGame::try_new(players, &serde_json::to_string(&setting).unwrap());
+        assert_eq!(error.err().unwrap().code, ErrorCode::InvalidSetting);
+        let error = WasmGame::try_new("[", "{}").err().unwrap();
+        assert_eq!(error.code, ErrorCode::MalformedInput);
+    }
+}
@@ -0,0 +1,96 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+};
+use thiserror::Error;
+
+use crate::{game::GameSetting, load::LoadError, load_pack};
+
+#[derive(Debug, Error)]
+pub enum WatchError {
+    #[error("{0}")]
+    Load(#[from] LoadError),
+    #[error("Could not watch card pack: {0}")]
+    Notify(#[from] notify::Error),
+}
+
+#[derive(Debug)]
+struct Loaded {
+    setting: GameSetting,
+    /// Why the last reload failed, cleared by the next good one.
+    error: Option<String>,
+}
+
+/// Card pack kept in sync with its file. Each change is loaded and validated again; a pack
+/// that fails to load leaves the previous setting in place, so games created meanwhile
+/// still get a playable one. Games already running keep the setting they started with.
+pub struct PackWatcher {
+    path: PathBuf,
+    loaded: Arc<RwLock<Loaded>>,
+    // Stops watching when dropped
+    _watcher: RecommendedWatcher,
+}
+
+impl PackWatcher {
+    /// Load the pack at `path` and watch it. The initial load must succeed.
+    pub fn new(path: &Path) -> Result<PackWatcher, WatchError> {
+        let path = path.to_path_buf();
+        let loaded = Arc::new(RwLock::new(Loaded {
+            setting: load_pack(&path)?.into_setting(),
+            error: None,
+        }));
+        let mut watcher = {
+            let (path, loaded) = (path.clone(), Arc::clone(&loaded));
+            notify::recommended_watcher(move |event: notify::Result<Event>| {
+                if let Ok(event) = event {
+                    if touches(&event, &path) {
+                        reload(&path, &loaded);
+                    }
+                }
+            })?
+        };
+        // Editors often save by replacing the file, which would end a watch on the file itself
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        watcher.watch(directory, RecursiveMode::NonRecursive)?;
+        Ok(PackWatcher {
+            path,
+            loaded,
+            _watcher: watcher,
+        })
+    }
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+    /// Setting from the last pack that loaded successfully.
+    pub fn get_setting(&self) -> GameSetting {
+        self.loaded.read().unwrap().setting.clone()
+    }
+    /// Why the latest change to the file could not be loaded, if it could not.
+    pub fn get_error(&self) -> Option<String> {
+        self.loaded.read().unwrap().error.clone()
+    }
+}
+
+fn touches(event: &Event, path: &Path) -> bool {
+    (event.kind.is_create() || event.kind.is_modify())
+        && event
+            .paths
+            .iter()
+            .any(|changed| changed.file_name() == path.file_name())
+}
+
+fn reload(path: &Path, loaded: &RwLock<Loaded>) {
+    let result = load_pack(path);
+    let mut loaded = loaded.write().unwrap();
+    match result {
+        Ok(pack) => {
+            loaded.setting = pack.into_setting();
+            loaded.error = None;
+        }
+        Err(error) => loaded.error = Some(error.to_string()),
+    }
+}